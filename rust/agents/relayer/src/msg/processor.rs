@@ -252,6 +252,7 @@ mod test {
         ChainConf {
             domain: domain.clone(),
             signer: Default::default(),
+            additional_signers: Default::default(),
             finality_blocks: Default::default(),
             addresses: Default::default(),
             connection: ChainConnectionConf::Ethereum(hyperlane_ethereum::ConnectionConf::Http {
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+/// Abort codes raised by the Hyperlane Move modules, as `(module, code, description)`
+const HYPERLANE_ABORT_CODES: &[(&str, u64, &str)] = &[
+    ("mailbox", 0, "sender is not the mailbox owner"),
+    ("mailbox", 1, "message body is too long"),
+    (
+        "mailbox",
+        2,
+        "message version does not match the mailbox version",
+    ),
+    ("mailbox", 3, "message destination is not this chain"),
+    ("mailbox", 4, "message was already delivered"),
+    ("mailbox", 5, "ISM rejected the message metadata"),
+    ("multisig_ism", 1, "sender is not the ISM owner"),
+    (
+        "multisig_ism",
+        2,
+        "not enough validator signatures to meet the threshold",
+    ),
+    (
+        "multisig_ism",
+        33,
+        "threshold is zero or larger than the validator set",
+    ),
+    ("ism_metadata", 1, "metadata has an invalid length"),
+    ("ism_metadata", 2, "signature has an invalid recovery id"),
+    ("igps", 1, "sender is not the IGP owner"),
+    ("igps", 2, "gas config lengths do not match"),
+    ("igps", 3, "insufficient interchain gas payment"),
+    ("igps", 4, "sender is not the IGP beneficiary"),
+    ("gas_oracle", 1, "sender is not the gas oracle owner"),
+    ("gas_oracle", 2, "gas data lengths do not match"),
+    ("validator_announce", 0, "announcement was already made"),
+    ("validator_announce", 1, "announcement signature is invalid"),
+    ("validator_announce", 2, "announcement account is invalid"),
+    (
+        "validator_announce",
+        3,
+        "announcement was not signed by the validator",
+    ),
+    ("merkle_tree", 0, "merkle tree exceeds its maximum depth"),
+    ("merkle_tree", 1, "merkle tree is full"),
+];
+
+/// A Move abort parsed from the VM status of a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAbort {
+    /// Name of the aborting module, without its address
+    pub module: String,
+    /// Code passed to `abort` or `assert!`
+    pub code: u64,
+}
+
+impl MoveAbort {
+    /// Parse a VM status such as
+    /// `Move abort in 0x1::mailbox: ERROR_ALREADY_DELIVERED(0x4): ` or
+    /// `Move abort in 0x1::mailbox: 0x4`. Returns `None` for other statuses.
+    pub fn from_vm_status(vm_status: &str) -> Option<Self> {
+        let (module_id, reason) = vm_status.strip_prefix("Move abort in ")?.split_once(": ")?;
+        let module = module_id.rsplit("::").next()?.to_owned();
+        let code = match reason.split_once("(0x") {
+            Some((_, code)) => code.split(')').next()?,
+            None => reason.strip_prefix("0x")?.split(':').next()?.trim(),
+        };
+        let code = u64::from_str_radix(code, 16).ok()?;
+        Some(Self { module, code })
+    }
+}
+
+/// Human readable descriptions of Move abort codes, keyed by module name and
+/// code. The default table covers the Hyperlane modules; deployments with
+/// their own modules can extend it with `with_description`.
+#[derive(Debug, Clone)]
+pub struct AbortCodeDescriptions {
+    descriptions: HashMap<(String, u64), String>,
+}
+
+impl Default for AbortCodeDescriptions {
+    fn default() -> Self {
+        let descriptions = HYPERLANE_ABORT_CODES
+            .iter()
+            .map(|(module, code, description)| {
+                (((*module).to_owned(), *code), (*description).to_owned())
+            })
+            .collect();
+        Self { descriptions }
+    }
+}
+
+impl AbortCodeDescriptions {
+    /// Add or replace the description of `code` raised by `module`
+    pub fn with_description(
+        mut self,
+        module: impl Into<String>,
+        code: u64,
+        description: impl Into<String>,
+    ) -> Self {
+        self.descriptions
+            .insert((module.into(), code), description.into());
+        self
+    }
+
+    /// The description of `abort`, if it is known
+    pub fn describe(&self, abort: &MoveAbort) -> Option<&str> {
+        self.descriptions
+            .get(&(abort.module.clone(), abort.code))
+            .map(String::as_str)
+    }
+
+    /// `vm_status` followed by the description of its abort code, if any
+    pub fn describe_vm_status(&self, vm_status: &str) -> String {
+        match MoveAbort::from_vm_status(vm_status)
+            .as_ref()
+            .and_then(|abort| self.describe(abort))
+        {
+            Some(description) => format!("{vm_status} ({description})"),
+            None => vm_status.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AbortCodeDescriptions, MoveAbort};
+
+    #[test]
+    fn test_parse_move_abort() {
+        assert_eq!(
+            MoveAbort::from_vm_status(
+                "Move abort in 0x61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb::mailbox: ERROR_ALREADY_DELIVERED(0x4): "
+            ),
+            Some(MoveAbort {
+                module: "mailbox".to_owned(),
+                code: 4
+            })
+        );
+        assert_eq!(
+            MoveAbort::from_vm_status("Move abort in 0xa11ce::multisig_ism: 0x21"),
+            Some(MoveAbort {
+                module: "multisig_ism".to_owned(),
+                code: 33
+            })
+        );
+        assert_eq!(MoveAbort::from_vm_status("Out of gas"), None);
+    }
+
+    #[test]
+    fn test_known_abort_code_is_described() {
+        let descriptions = AbortCodeDescriptions::default();
+        assert_eq!(
+            descriptions.describe_vm_status("Move abort in 0xa11ce::mailbox: 0x4"),
+            "Move abort in 0xa11ce::mailbox: 0x4 (message was already delivered)"
+        );
+        assert_eq!(
+            descriptions.describe_vm_status("Move abort in 0xa11ce::router: 0x3"),
+            "Move abort in 0xa11ce::router: 0x3"
+        );
+
+        let descriptions = descriptions.with_description("router", 3, "no router enrolled");
+        assert_eq!(
+            descriptions.describe(&MoveAbort {
+                module: "router".to_owned(),
+                code: 3
+            }),
+            Some("no router enrolled")
+        );
+    }
+}
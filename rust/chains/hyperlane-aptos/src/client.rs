@@ -1,21 +1,307 @@
-use aptos_sdk::rest_client::Client;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::rest_client::{
+    aptos_api_types::{
+        Block, GasEstimation, MoveModuleBytecode, Transaction, UserTransaction, VersionedEvent,
+        ViewRequest,
+    },
+    error::RestError,
+    Account, Client, Resource, Response, State,
+};
+use aptos_sdk::types::{account_address::AccountAddress, transaction::SignedTransaction};
+use async_trait::async_trait;
+use hyperlane_core::HyperlaneDomain;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 use url::Url;
 
+use crate::AptosRpcMetrics;
+
+/// How long a fetched block number is reused before asking the node again
+pub(crate) const BLOCK_NUMBER_TTL: Duration = Duration::from_secs(1);
+
+/// Block number caches shared by the clients of each domain, keyed by domain id
+static SHARED_BLOCK_NUMBER_CACHES: Lazy<std::sync::Mutex<HashMap<u32, Arc<BlockNumberCache>>>> =
+    Lazy::new(Default::default);
+
+/// The latest block number, reused for a short window so that indexers
+/// polling for the chain tip don't each cost a request
+#[derive(Default)]
+pub(crate) struct BlockNumberCache {
+    latest: Mutex<Option<(Instant, u32)>>,
+}
+
+impl BlockNumberCache {
+    /// The cache shared by every client of `domain`
+    pub(crate) fn shared(domain: &HyperlaneDomain) -> Arc<Self> {
+        SHARED_BLOCK_NUMBER_CACHES
+            .lock()
+            .unwrap()
+            .entry(domain.id())
+            .or_default()
+            .clone()
+    }
+
+    /// Return the cached block number if it was fetched less than `ttl` ago,
+    /// otherwise `fetch` and cache a fresh one. Concurrent callers wait for a
+    /// single in-flight fetch rather than issuing their own.
+    pub(crate) async fn get_or_fetch<E, Fut>(
+        &self,
+        ttl: Duration,
+        fetch: impl FnOnce() -> Fut,
+    ) -> Result<u32, E>
+    where
+        Fut: Future<Output = Result<u32, E>>,
+    {
+        let mut latest = self.latest.lock().await;
+        if let Some((fetched_at, block_number)) = *latest {
+            if fetched_at.elapsed() < ttl {
+                return Ok(block_number);
+            }
+        }
+        let block_number = fetch().await?;
+        *latest = Some((Instant::now(), block_number));
+        Ok(block_number)
+    }
+}
+
+/// The Aptos REST API methods used by this crate. `Client` is the production
+/// implementation; tests can stub node responses with their own.
+#[async_trait]
+pub trait AptosRpc: Send + Sync {
+    /// Latest ledger state of the node
+    async fn get_ledger_information(&self) -> Result<Response<State>, RestError>;
+
+    /// Gas unit price estimate of the node
+    async fn estimate_gas_price(&self) -> Result<Response<GasEstimation>, RestError>;
+
+    /// Account with its current sequence number
+    async fn get_account(&self, address: AccountAddress) -> Result<Response<Account>, RestError>;
+
+    /// Events of the event handle `field_name` of the resource `struct_tag`,
+    /// starting at sequence number `start`, or the latest page if `None`
+    async fn get_account_events(
+        &self,
+        address: AccountAddress,
+        struct_tag: &str,
+        field_name: &str,
+        start: Option<u64>,
+        limit: Option<u16>,
+    ) -> Result<Response<Vec<VersionedEvent>>, RestError>;
+
+    /// Module `module_name` published under `address`
+    async fn get_account_module(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+    ) -> Result<Response<MoveModuleBytecode>, RestError>;
+
+    /// Modules published under `address`
+    async fn get_account_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<Response<Vec<MoveModuleBytecode>>, RestError>;
+
+    /// Resource `resource_type` stored under `address`, if any
+    async fn get_account_resource(
+        &self,
+        address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Response<Option<Resource>>, RestError>;
+
+    /// Block at `height`
+    async fn get_block_by_height(
+        &self,
+        height: u64,
+        with_transactions: bool,
+    ) -> Result<Response<Block>, RestError>;
+
+    /// Transaction with `hash`
+    async fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Response<Transaction>, RestError>;
+
+    /// Simulate `txn` without committing it
+    async fn simulate(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Vec<UserTransaction>>, RestError>;
+
+    /// Submit `txn` and wait until it is committed
+    async fn submit_and_wait(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Transaction>, RestError>;
+
+    /// Call the view function of `request`
+    async fn view(
+        &self,
+        request: &ViewRequest,
+        version: Option<u64>,
+    ) -> Result<Response<Vec<serde_json::Value>>, RestError>;
+
+    /// Url of the node, including its path prefix
+    fn path_prefix_string(&self) -> String;
+}
+
+#[async_trait]
+impl AptosRpc for Client {
+    async fn get_ledger_information(&self) -> Result<Response<State>, RestError> {
+        Client::get_ledger_information(self).await
+    }
+
+    async fn estimate_gas_price(&self) -> Result<Response<GasEstimation>, RestError> {
+        Client::estimate_gas_price(self).await
+    }
+
+    async fn get_account(&self, address: AccountAddress) -> Result<Response<Account>, RestError> {
+        Client::get_account(self, address).await
+    }
+
+    async fn get_account_events(
+        &self,
+        address: AccountAddress,
+        struct_tag: &str,
+        field_name: &str,
+        start: Option<u64>,
+        limit: Option<u16>,
+    ) -> Result<Response<Vec<VersionedEvent>>, RestError> {
+        Client::get_account_events(self, address, struct_tag, field_name, start, limit).await
+    }
+
+    async fn get_account_module(
+        &self,
+        address: AccountAddress,
+        module_name: &str,
+    ) -> Result<Response<MoveModuleBytecode>, RestError> {
+        Client::get_account_module(self, address, module_name).await
+    }
+
+    async fn get_account_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<Response<Vec<MoveModuleBytecode>>, RestError> {
+        Client::get_account_modules(self, address).await
+    }
+
+    async fn get_account_resource(
+        &self,
+        address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Response<Option<Resource>>, RestError> {
+        Client::get_account_resource(self, address, resource_type).await
+    }
+
+    async fn get_block_by_height(
+        &self,
+        height: u64,
+        with_transactions: bool,
+    ) -> Result<Response<Block>, RestError> {
+        Client::get_block_by_height(self, height, with_transactions).await
+    }
+
+    async fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+    ) -> Result<Response<Transaction>, RestError> {
+        Client::get_transaction_by_hash(self, hash).await
+    }
+
+    async fn simulate(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Vec<UserTransaction>>, RestError> {
+        Client::simulate(self, txn).await
+    }
+
+    async fn submit_and_wait(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Transaction>, RestError> {
+        Client::submit_and_wait(self, txn).await
+    }
+
+    async fn view(
+        &self,
+        request: &ViewRequest,
+        version: Option<u64>,
+    ) -> Result<Response<Vec<serde_json::Value>>, RestError> {
+        Client::view(self, request, version).await
+    }
+
+    fn path_prefix_string(&self) -> String {
+        Client::path_prefix_string(self)
+    }
+}
+
 /// Aptos RPC client
-pub struct AptosClient(Client);
+#[derive(Clone)]
+pub struct AptosClient {
+    inner: Arc<dyn AptosRpc>,
+    metrics: Option<(AptosRpcMetrics, String)>,
+    block_number_cache: Arc<BlockNumberCache>,
+}
 impl AptosClient {
     /// Create a new aptos rpc client from node url
     pub fn new(rpc_endpoint: String) -> Self {
-        Self(Client::new(Url::from_str(&rpc_endpoint).unwrap()))
+        Self::from_rpc(Arc::new(Client::new(Url::from_str(&rpc_endpoint).unwrap())))
+    }
+
+    /// Create a client sending its requests through `rpc`
+    pub fn from_rpc(rpc: Arc<dyn AptosRpc>) -> Self {
+        Self {
+            inner: rpc,
+            metrics: None,
+            block_number_cache: Default::default(),
+        }
+    }
+
+    /// Create a new aptos rpc client for `domain`. All clients of a domain
+    /// share one block number cache, so they make a single request for the
+    /// chain tip between them.
+    pub fn for_domain(rpc_endpoint: String, domain: &HyperlaneDomain) -> Self {
+        Self {
+            block_number_cache: BlockNumberCache::shared(domain),
+            ..Self::new(rpc_endpoint)
+        }
+    }
+
+    /// Record request metrics for calls made to `domain`
+    pub fn with_metrics(mut self, metrics: AptosRpcMetrics, domain: &HyperlaneDomain) -> Self {
+        self.metrics = Some((metrics, domain.name().to_owned()));
+        self
+    }
+
+    /// Await `request`, recording it as a call to `method` if metrics are enabled
+    pub(crate) async fn instrumented<T, E>(
+        &self,
+        method: &str,
+        request: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let res = request.await;
+        if let Some((metrics, chain)) = &self.metrics {
+            metrics.observe(chain, method, res.is_ok(), start.elapsed());
+        }
+        res
+    }
+
+    /// Cache of the latest block number
+    pub(crate) fn block_number_cache(&self) -> &BlockNumberCache {
+        &self.block_number_cache
     }
 }
 
 impl std::ops::Deref for AptosClient {
-    type Target = Client;
+    type Target = dyn AptosRpc;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &*self.inner
     }
 }
 
@@ -24,3 +310,114 @@ impl std::fmt::Debug for AptosClient {
         f.write_str("AptosClient { ... }")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneDomainProtocol, HyperlaneDomainType, KnownHyperlaneDomain,
+    };
+    use prometheus::{histogram_opts, opts, HistogramVec, IntCounterVec};
+
+    use super::{AptosClient, BlockNumberCache, BLOCK_NUMBER_TTL};
+    use crate::{
+        AptosRpcMetrics, APTOS_RPC_REQUEST_COUNT_LABELS, APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS,
+    };
+
+    #[tokio::test]
+    async fn test_instrumented_request_increments_counter() {
+        let request_count = IntCounterVec::new(
+            opts!("request_count", "help"),
+            APTOS_RPC_REQUEST_COUNT_LABELS,
+        )
+        .unwrap();
+        let request_duration_seconds = HistogramVec::new(
+            histogram_opts!("request_duration_seconds", "help"),
+            APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS,
+        )
+        .unwrap();
+        let domain = HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1);
+        let client = AptosClient::new("http://127.0.0.1:8080/v1".to_string()).with_metrics(
+            AptosRpcMetrics::new(request_count.clone(), request_duration_seconds.clone()),
+            &domain,
+        );
+
+        let chain = domain.name();
+        client
+            .instrumented("view", async { Ok::<_, ()>(()) })
+            .await
+            .unwrap();
+        client
+            .instrumented("view", async { Err::<(), _>(()) })
+            .await
+            .unwrap_err();
+
+        for status in ["success", "failure"] {
+            let labels = [chain, "view", status];
+            assert_eq!(request_count.with_label_values(&labels).get(), 1);
+            assert_eq!(
+                request_duration_seconds
+                    .with_label_values(&labels)
+                    .get_sample_count(),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_number_cache_reuses_recent_fetch() {
+        let cache = BlockNumberCache::default();
+        let fetches = &AtomicUsize::new(0);
+        let fetch =
+            || async move { Ok::<_, ()>(100 + fetches.fetch_add(1, Ordering::SeqCst) as u32) };
+
+        assert_eq!(cache.get_or_fetch(BLOCK_NUMBER_TTL, fetch).await, Ok(100));
+        assert_eq!(cache.get_or_fetch(BLOCK_NUMBER_TTL, fetch).await, Ok(100));
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // an expired entry is refreshed
+        assert_eq!(cache.get_or_fetch(Duration::ZERO, fetch).await, Ok(101));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_clients_of_a_domain_share_block_number_fetches() {
+        let domain = |domain_id| HyperlaneDomain::Unknown {
+            domain_id,
+            domain_name: format!("aptostest{domain_id}"),
+            domain_type: HyperlaneDomainType::LocalTestChain,
+            domain_protocol: HyperlaneDomainProtocol::Aptos,
+        };
+        let url = || "http://127.0.0.1:8080/v1".to_string();
+        let first = AptosClient::for_domain(url(), &domain(0xa11ce));
+        let second = AptosClient::for_domain(url(), &domain(0xa11ce));
+        let other = AptosClient::for_domain(url(), &domain(0xb0b));
+
+        let fetches = &AtomicUsize::new(0);
+        let fetch =
+            || async move { Ok::<_, ()>(100 + fetches.fetch_add(1, Ordering::SeqCst) as u32) };
+
+        for client in [&first, &second] {
+            assert_eq!(
+                client
+                    .block_number_cache()
+                    .get_or_fetch(BLOCK_NUMBER_TTL, fetch)
+                    .await,
+                Ok(100)
+            );
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // clients of another domain have their own cache
+        assert_eq!(
+            other
+                .block_number_cache()
+                .get_or_fetch(BLOCK_NUMBER_TTL, fetch)
+                .await,
+            Ok(101)
+        );
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+}
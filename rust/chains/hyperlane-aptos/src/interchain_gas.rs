@@ -6,11 +6,14 @@ use async_trait::async_trait;
 use hyperlane_core::{
     ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
     HyperlaneDomain, HyperlaneProvider, Indexer, InterchainGasPaymaster, InterchainGasPayment,
-    LogMeta, SequenceIndexer, H256,
+    LogMeta, SequenceIndexer, H256, U256,
 };
 use tracing::{info, instrument};
 
-use crate::{get_filtered_events, AptosHpProvider, ConnectionConf, GasPaymentEventData};
+use crate::{
+    get_filtered_events, utils, AptosHpProvider, AptosRpcMetrics, ConnectionConf,
+    GasPaymentEventData,
+};
 
 use crate::AptosClient;
 use aptos_sdk::types::account_address::AccountAddress;
@@ -20,26 +23,55 @@ use aptos_sdk::types::account_address::AccountAddress;
 pub struct AptosInterchainGasPaymaster {
     domain: HyperlaneDomain,
     package_address: AccountAddress,
-    aptos_client_url: String,
+    aptos_client: AptosClient,
 }
 
 impl AptosInterchainGasPaymaster {
     /// Create a new Aptos IGP.
     pub fn new(conf: &ConnectionConf, locator: &ContractLocator) -> Self {
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
-        let aptos_client_url = conf.url.to_string();
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(locator, aptos_client)
+    }
+
+    /// Create an Aptos IGP sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(locator: &ContractLocator, aptos_client: AptosClient) -> Self {
+        Self::from_client(locator, aptos_client)
+    }
+
+    fn from_client(locator: &ContractLocator, aptos_client: AptosClient) -> Self {
+        let package_address = utils::h256_to_account_address(&locator.address);
         Self {
             package_address,
             domain: locator.domain.clone(),
-            aptos_client_url,
+            aptos_client,
         }
     }
+
+    /// Record metrics for the requests this IGP makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
+
+    /// The account gas payments are deposited to.
+    /// Payments are transferred on `pay_for_gas`, so there is nothing to claim.
+    #[instrument(err, ret, skip(self))]
+    pub async fn beneficiary(&self) -> ChainResult<H256> {
+        let beneficiary = utils::get_resource_field(
+            &self.aptos_client,
+            self.package_address,
+            &format!("{}::igps::IgpState", self.package_address.to_hex_literal()),
+            "beneficiary",
+        )
+        .await?;
+        utils::FromViewReturn::from_view_return(&beneficiary)
+    }
 }
 
 impl HyperlaneContract for AptosInterchainGasPaymaster {
     fn address(&self) -> H256 {
-        self.package_address.into_bytes().into()
+        utils::account_address_to_h256(&self.package_address)
     }
 }
 
@@ -49,9 +81,9 @@ impl HyperlaneChain for AptosInterchainGasPaymaster {
     }
 
     fn provider(&self) -> Box<dyn HyperlaneProvider> {
-        Box::new(AptosHpProvider::new(
+        Box::new(AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client_url.clone(),
+            self.aptos_client.clone(),
         ))
     }
 }
@@ -61,6 +93,7 @@ impl InterchainGasPaymaster for AptosInterchainGasPaymaster {}
 /// Struct that retrieves event data for a Aptos IGP contract
 #[derive(Debug)]
 pub struct AptosInterchainGasPaymasterIndexer {
+    domain: HyperlaneDomain,
     aptos_client: AptosClient,
     package_address: AccountAddress,
 }
@@ -68,14 +101,135 @@ pub struct AptosInterchainGasPaymasterIndexer {
 impl AptosInterchainGasPaymasterIndexer {
     /// Create a new Aptos IGP indexer.
     pub fn new(conf: &ConnectionConf, locator: ContractLocator) -> Self {
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
-        let aptos_client = AptosClient::new(conf.url.to_string());
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(locator, aptos_client)
+    }
+
+    /// Create an Aptos IGP indexer sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(locator: ContractLocator, aptos_client: AptosClient) -> Self {
+        Self::from_client(locator, aptos_client)
+    }
+
+    pub(crate) fn from_client(locator: ContractLocator, aptos_client: AptosClient) -> Self {
+        let package_address = utils::h256_to_account_address(&locator.address);
         Self {
+            domain: locator.domain.clone(),
             aptos_client,
             package_address,
         }
     }
+
+    /// Record metrics for the requests this indexer makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
+}
+
+impl AptosInterchainGasPaymasterIndexer {
+    /// Fetch the gas payment made for `message_id`, if any.
+    #[instrument(err, skip(self))]
+    pub async fn fetch_payment_for_message(
+        &self,
+        message_id: H256,
+    ) -> ChainResult<Option<InterchainGasPayment>> {
+        let payments = self.fetch_gas_payment_events().await?;
+        find_payment_for_message(payments, message_id)
+    }
+
+    /// Whether the payments indexed for `message_id` cover the quote
+    /// for the gas they paid for on `destination_domain`.
+    /// Returns `false` if no payment was made.
+    #[instrument(err, ret, skip(self))]
+    pub async fn is_sufficiently_paid(
+        &self,
+        message_id: H256,
+        destination_domain: u32,
+    ) -> ChainResult<bool> {
+        let payments = self.fetch_gas_payment_events().await?;
+        let Some(total) = total_payment_for_message(payments, message_id)? else {
+            return Ok(false);
+        };
+        let quote = self
+            .quote_gas_payment(destination_domain, total.gas_amount)
+            .await?;
+        Ok(total.payment >= quote)
+    }
+
+    /// Quote the payment required for `gas_amount` on `destination_domain`.
+    #[instrument(err, ret, skip(self))]
+    pub async fn quote_gas_payment(
+        &self,
+        destination_domain: u32,
+        gas_amount: U256,
+    ) -> ChainResult<U256> {
+        utils::send_view_request_decoded(
+            &self.aptos_client,
+            self.package_address.to_hex_literal(),
+            "igps".to_string(),
+            "quote_gas_payment".to_string(),
+            vec![],
+            vec![
+                serde_json::json!(destination_domain),
+                serde_json::json!(gas_amount.to_string()),
+            ],
+        )
+        .await
+    }
+
+    async fn fetch_gas_payment_events(&self) -> ChainResult<Vec<GasPaymentEventData>> {
+        let struct_tag = format!("{}::igps::IgpState", self.package_address.to_hex_literal());
+        let events = utils::get_all_account_events(
+            &self.aptos_client,
+            self.package_address,
+            &struct_tag,
+            "gas_payment_events",
+        )
+        .await?;
+
+        events
+            .into_iter()
+            .map(GasPaymentEventData::try_from)
+            .collect()
+    }
+}
+
+/// Find the gas payment for `message_id` among decoded gas payment events
+fn find_payment_for_message(
+    payments: Vec<GasPaymentEventData>,
+    message_id: H256,
+) -> ChainResult<Option<InterchainGasPayment>> {
+    for payment in payments {
+        let payment: InterchainGasPayment = payment.try_into()?;
+        if payment.message_id == message_id {
+            return Ok(Some(payment));
+        }
+    }
+    Ok(None)
+}
+
+/// Sum all gas payments made for `message_id`, if any
+fn total_payment_for_message(
+    payments: Vec<GasPaymentEventData>,
+    message_id: H256,
+) -> ChainResult<Option<InterchainGasPayment>> {
+    let mut total: Option<InterchainGasPayment> = None;
+    for payment in payments {
+        let payment: InterchainGasPayment = payment.try_into()?;
+        if payment.message_id != message_id {
+            continue;
+        }
+        total = Some(match total {
+            Some(total) => InterchainGasPayment {
+                message_id,
+                payment: total.payment.saturating_add(payment.payment),
+                gas_amount: total.gas_amount.saturating_add(payment.gas_amount),
+            },
+            None => payment,
+        });
+    }
+    Ok(total)
 }
 
 #[async_trait]
@@ -97,14 +251,7 @@ impl Indexer<InterchainGasPayment> for AptosInterchainGasPaymasterIndexer {
 
     #[instrument(level = "debug", err, ret, skip(self))]
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        let chain_state = self
-            .aptos_client
-            .get_ledger_information()
-            .await
-            .map_err(ChainCommunicationError::from_other)
-            .unwrap()
-            .into_inner();
-        Ok(chain_state.block_height as u32)
+        utils::get_finalized_block_number(&self.aptos_client).await
     }
 }
 
@@ -115,3 +262,104 @@ impl SequenceIndexer<InterchainGasPayment> for AptosInterchainGasPaymasterIndexe
         Ok((None, tip))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hyperlane_core::{ContractLocator, HyperlaneDomain, KnownHyperlaneDomain, H256, U256};
+
+    use super::{
+        find_payment_for_message, total_payment_for_message, AptosInterchainGasPaymasterIndexer,
+    };
+    use crate::mock::MockAptosRpc;
+    use crate::{utils, AptosClient, GasPaymentEventData};
+
+    fn gas_payment_event(message_id: H256, required_payment: u64) -> GasPaymentEventData {
+        GasPaymentEventData {
+            message_id: format!("{:?}", message_id),
+            gas_amount: "100000".to_string(),
+            required_payment: required_payment.to_string(),
+            block_height: "1".to_string(),
+            transaction_hash: "0x01".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_payment_for_message_pages_through_all_events() {
+        let igp = H256::repeat_byte(0x33);
+        // the payment is the oldest event, so it isn't on the latest page
+        let payments = (0..150u8)
+            .map(|i| {
+                serde_json::to_value(gas_payment_event(H256::repeat_byte(i), i.into())).unwrap()
+            })
+            .collect();
+        let rpc = Arc::new(
+            MockAptosRpc::default()
+                .with_resource(
+                    &format!(
+                        "{}::igps::IgpState",
+                        utils::h256_to_account_address(&igp).to_hex_literal()
+                    ),
+                    serde_json::json!({ "gas_payment_events": { "counter": "150" } }),
+                )
+                .with_events("gas_payment_events", payments),
+        );
+        let indexer = AptosInterchainGasPaymasterIndexer::with_client(
+            ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+                address: igp,
+            },
+            AptosClient::from_rpc(rpc.clone()),
+        );
+
+        let payment = indexer
+            .fetch_payment_for_message(H256::repeat_byte(0))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payment.payment, U256::zero());
+        assert_eq!(
+            rpc.requests("get_account_events"),
+            vec!["gas_payment_events@Some(0)", "gas_payment_events@Some(100)"]
+        );
+    }
+
+    #[test]
+    fn test_find_payment_for_message() {
+        let payments = vec![
+            gas_payment_event(H256::repeat_byte(1), 10),
+            gas_payment_event(H256::repeat_byte(2), 20),
+        ];
+
+        let payment = find_payment_for_message(payments.clone(), H256::repeat_byte(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(payment.message_id, H256::repeat_byte(2));
+        assert_eq!(payment.payment, U256::from(20));
+
+        assert!(find_payment_for_message(payments, H256::repeat_byte(3))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_total_payment_for_message() {
+        let payments = vec![
+            gas_payment_event(H256::repeat_byte(1), 10),
+            gas_payment_event(H256::repeat_byte(2), 20),
+            gas_payment_event(H256::repeat_byte(1), 15),
+        ];
+
+        let total = total_payment_for_message(payments.clone(), H256::repeat_byte(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(total.message_id, H256::repeat_byte(1));
+        assert_eq!(total.payment, U256::from(25));
+        assert_eq!(total.gas_amount, U256::from(200000));
+
+        assert!(total_payment_for_message(payments, H256::repeat_byte(3))
+            .unwrap()
+            .is_none());
+    }
+}
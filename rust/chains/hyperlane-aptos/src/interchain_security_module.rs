@@ -8,15 +8,16 @@ use hyperlane_core::{
     HyperlaneMessage, InterchainSecurityModule, ModuleType, H256, U256,
 };
 
+use crate::multisig_ism;
 use crate::utils;
 use crate::AptosClient;
+use crate::AptosRpcMetrics;
 use crate::ConnectionConf;
 
 use aptos_sdk::types::account_address::AccountAddress;
 
 /// A reference to an InterchainSecurityModule contract on some Sealevel chain
 #[allow(unused)]
-#[derive(Debug)]
 pub struct AptosInterchainSecurityModule {
     aptos_client: AptosClient,
     package_address: AccountAddress,
@@ -27,9 +28,32 @@ pub struct AptosInterchainSecurityModule {
 impl AptosInterchainSecurityModule {
     /// Create a new sealevel InterchainSecurityModule
     pub fn new(conf: &ConnectionConf, locator: ContractLocator, payer: Option<Keypair>) -> Self {
-        let aptos_client = AptosClient::new(conf.url.to_string());
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(locator, payer, aptos_client)
+    }
+
+    /// Create an InterchainSecurityModule sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        Self::from_client(locator, payer, aptos_client)
+    }
+
+    /// Record metrics for the requests this InterchainSecurityModule makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
+
+    pub(crate) fn from_client(
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        let package_address = utils::h256_to_account_address(&locator.address);
         Self {
             aptos_client,
             payer,
@@ -39,9 +63,15 @@ impl AptosInterchainSecurityModule {
     }
 }
 
+impl std::fmt::Debug for AptosInterchainSecurityModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self as &dyn HyperlaneContract)
+    }
+}
+
 impl HyperlaneContract for AptosInterchainSecurityModule {
     fn address(&self) -> H256 {
-        self.package_address.into_bytes().into()
+        utils::account_address_to_h256(&self.package_address)
     }
 }
 
@@ -51,9 +81,9 @@ impl HyperlaneChain for AptosInterchainSecurityModule {
     }
 
     fn provider(&self) -> Box<dyn hyperlane_core::HyperlaneProvider> {
-        Box::new(crate::AptosHpProvider::new(
+        Box::new(crate::AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client.path_prefix_string(),
+            self.aptos_client.clone(),
         ))
     }
 }
@@ -61,7 +91,7 @@ impl HyperlaneChain for AptosInterchainSecurityModule {
 #[async_trait]
 impl InterchainSecurityModule for AptosInterchainSecurityModule {
     async fn module_type(&self) -> ChainResult<ModuleType> {
-        let view_response = utils::send_view_request(
+        let view_result: u64 = utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "multisig_ism".to_string(),
@@ -71,11 +101,6 @@ impl InterchainSecurityModule for AptosInterchainSecurityModule {
         )
         .await?;
 
-        let view_result: u64 = serde_json::from_str::<String>(&view_response[0].to_string())
-            .unwrap()
-            .parse()
-            .unwrap();
-
         if let Some(module_type) = ModuleType::from_u64(view_result) {
             Ok(module_type)
         } else {
@@ -86,10 +111,49 @@ impl InterchainSecurityModule for AptosInterchainSecurityModule {
 
     async fn dry_run_verify(
         &self,
-        _message: &HyperlaneMessage,
-        _metadata: &[u8],
+        message: &HyperlaneMessage,
+        metadata: &[u8],
     ) -> ChainResult<Option<U256>> {
-        // TODO: Implement this once we have aggregation ISM support in Aptos
-        Ok(Some(U256::zero()))
+        // Aptos only has the multisig ISM, so verification is checked locally
+        // against its validator set instead of simulating `handle_message`
+        let (validators, threshold) = multisig_ism::fetch_validators_and_threshold(
+            &self.aptos_client,
+            self.package_address,
+            message.origin,
+        )
+        .await?;
+        Ok(
+            multisig_ism::multisig_metadata_verifies(message, metadata, &validators, threshold)
+                .then(U256::zero),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{ContractLocator, HyperlaneDomain, KnownHyperlaneDomain, H256};
+    use solana_sdk::signature::Keypair;
+
+    use super::AptosInterchainSecurityModule;
+    use crate::ConnectionConf;
+
+    #[test]
+    fn test_debug_omits_signer() {
+        let conf = ConnectionConf::test_default();
+        let payer = Keypair::new();
+        let secret = payer.secret().to_bytes();
+        let ism = AptosInterchainSecurityModule::new(
+            &conf,
+            ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+                address: H256::repeat_byte(1),
+            },
+            Some(payer),
+        );
+
+        let debug = format!("{:?}", ism);
+        assert!(debug.contains(&format!("{:?}", H256::repeat_byte(1))));
+        assert!(!debug.contains(&format!("{:?}", &secret[..])));
+        assert!(!debug.contains(&hex::encode(secret)));
     }
 }
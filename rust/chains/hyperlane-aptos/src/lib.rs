@@ -5,10 +5,12 @@
 #![deny(warnings)]
 
 pub use crate::multisig_ism::*;
-pub use client::AptosClient;
+pub use abort_codes::*;
+pub use client::{AptosClient, AptosRpc};
 pub use interchain_gas::*;
 pub use interchain_security_module::*;
 pub use mailbox::*;
+pub use metrics::*;
 pub use provider::*;
 pub use solana_sdk::signer::keypair::Keypair;
 pub use trait_builder::*;
@@ -16,9 +18,11 @@ pub use types::*;
 pub use utils::*;
 pub use validator_announce::*;
 
+mod abort_codes;
 mod interchain_gas;
 mod interchain_security_module;
 mod mailbox;
+mod metrics;
 mod multisig_ism;
 mod provider;
 mod trait_builder;
@@ -26,4 +30,7 @@ mod types;
 mod utils;
 
 mod client;
+/// Mocked Aptos node for tests
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 mod validator_announce;
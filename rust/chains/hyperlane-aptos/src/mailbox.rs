@@ -1,40 +1,57 @@
 #![allow(warnings)] // FIXME remove
 
 use std::ops::RangeInclusive;
-use std::{collections::HashMap, num::NonZeroU64, str::FromStr as _};
+use std::{
+    collections::HashMap,
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        RwLock,
+    },
+};
 
 use aptos_sdk::move_types::identifier::Identifier;
 use async_trait::async_trait;
 use borsh::{BorshDeserialize, BorshSerialize};
+use futures_util::stream::{self, StreamExt as _, TryStreamExt as _};
 use hyperlane_core::SequenceIndexer;
 use jsonrpc_core::futures_util::TryFutureExt;
 use jsonrpc_core::Middleware;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, timeout, Instant};
 use tracing::{debug, info, instrument, warn};
 
 use hyperlane_core::{
     accumulator::incremental::IncrementalMerkle, ChainCommunicationError, ChainResult, Checkpoint,
     ContractLocator, Decode as _, Encode as _, HyperlaneAbi, HyperlaneChain, HyperlaneContract,
-    HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Indexer, LogMeta, Mailbox,
-    TxCostEstimate, TxOutcome, H256, H512, U256,
+    HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, Indexer, InterchainSecurityModule,
+    LogMeta, Mailbox, TxCostEstimate, TxOutcome, H256, H512, U256,
 };
 
 use crate::{
-    convert_keypair_to_aptos_account, get_filtered_events, simulate_aptos_transaction, utils,
-    AptosHpProvider, ConnectionConf, MsgProcessEventData, GAS_UNIT_PRICE,
+    convert_keypair_to_aptos_account, get_filtered_events, utils, AbortCodeDescriptions,
+    AptosHpProvider, AptosInterchainGasPaymasterIndexer, AptosInterchainSecurityModule,
+    ConnectionConf, MsgProcessEventData, PROCESS_TRANSACTION_OVERHEAD,
 };
 
 use solana_sdk::signature::Keypair;
 
-use crate::types::{DispatchEventData, MoveMerkleTree};
+use crate::multisig_ism::{MULTISIG_SIGNATURES_OFFSET, MULTISIG_SIGNATURE_LENGTH};
+use crate::types::DispatchEventData;
 use crate::utils::{convert_hex_string_to_h256, send_aptos_transaction};
-use crate::AptosClient;
+use crate::{AptosClient, AptosRpcMetrics};
 
 use aptos_sdk::{
     crypto::ed25519::Ed25519PrivateKey,
     crypto::ed25519::Ed25519PublicKey,
     move_types::{ident_str, language_storage::ModuleId},
     rest_client::{
-        aptos_api_types::{EntryFunctionId, VersionedEvent, ViewRequest},
+        aptos_api_types::{
+            EntryFunctionId, Transaction as AptosTransaction, TransactionInfo, VersionedEvent,
+            ViewRequest,
+        },
         Client, FaucetClient,
     },
     transaction_builder::TransactionFactory,
@@ -48,12 +65,320 @@ use aptos_sdk::{
     },
 };
 
+/// Errors raised by the Aptos mailbox before a transaction is submitted
+#[derive(thiserror::Error, Debug)]
+pub enum AptosMailboxError {
+    /// The `process` transaction would exceed the transaction size limit
+    #[error(
+        "Process transaction is an estimated {0} bytes, exceeding the maximum transaction size of {1} bytes"
+    )]
+    TransactionTooLarge(usize, u32),
+    /// Processing would consume more gas than the configured cap
+    #[error("Processing needs more than the configured maximum of {0} gas units")]
+    GasBudgetExceeded(u64),
+    /// Gas payment enforcement is enabled, but no gas paymaster was configured
+    #[error("Gas payment enforcement requires an interchain gas paymaster")]
+    MissingGasPaymaster,
+    /// The payments for the message don't cover the quote for its gas amount
+    #[error("Message {0:?} is not sufficiently paid for")]
+    Underpaid(H256),
+    /// The recipient module has no `handle_message` function
+    #[error("Recipient module has no `handle_message` function")]
+    MissingHandleMessage,
+    /// The recipient's `handle_message` doesn't take the message and metadata bytes
+    #[error("Unsupported `handle_message` parameters ({0}), expected (vector<u8>, vector<u8>)")]
+    UnsupportedHandleMessageParams(String),
+    /// No message has been dispatched, so there is no checkpoint yet
+    #[error("Outbox is empty, cannot compute checkpoint")]
+    EmptyOutbox,
+    /// The outbox holds more messages than a checkpoint index can represent
+    #[error("Outbox count {0} overflows a checkpoint index")]
+    OutboxCountOverflow(usize),
+    /// The recipient has no handler module registered with the router, e.g.
+    /// because it isn't an Aptos package address
+    #[error("Recipient {0} is not a registered Aptos package")]
+    UnregisteredRecipient(AccountAddress),
+    /// The module name registered for the recipient isn't a Move identifier
+    #[error("Recipient {0} is registered with invalid module name `{1}`")]
+    InvalidModuleName(AccountAddress, String),
+    /// The metadata is shorter than the multisig origin mailbox and merkle root
+    #[error("Metadata is {0} bytes, shorter than the {1} byte multisig header")]
+    MetadataTooShort(usize, usize),
+    /// The signatures don't split into whole 65 byte signatures
+    #[error("Metadata signatures span {0} bytes, which is not a multiple of {1}")]
+    MalformedSignatures(usize, usize),
+    /// The metadata carries no validator signatures
+    #[error("Metadata carries no validator signatures")]
+    MissingSignatures,
+    /// The mailbox reports the message as delivered but emitted no process
+    /// event for it
+    #[error("Message {0:?} is delivered but has no process event")]
+    MissingProcessEvent(H256),
+}
+
+impl From<AptosMailboxError> for ChainCommunicationError {
+    fn from(err: AptosMailboxError) -> Self {
+        ChainCommunicationError::from_other(err)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+struct AptosSimulationError(#[from] anyhow::Error);
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+struct AptosModuleAbiError(#[from] anyhow::Error);
+
+/// Ensure the `handle_message` transaction carrying `encoded_message` and
+/// `metadata` stays within `max_transaction_size`, since the node would
+/// otherwise reject it.
+fn validate_transaction_size(
+    encoded_message: &[u8],
+    metadata: &[u8],
+    max_transaction_size: u32,
+) -> Result<(), AptosMailboxError> {
+    let size = encoded_message.len() + metadata.len() + PROCESS_TRANSACTION_OVERHEAD;
+    if size > max_transaction_size as usize {
+        return Err(AptosMailboxError::TransactionTooLarge(
+            size,
+            max_transaction_size,
+        ));
+    }
+    Ok(())
+}
+
+/// Decode the structure of multisig ISM metadata, returning its number of
+/// signatures. This catches metadata the ISM would abort on before paying gas.
+fn validate_multisig_metadata(metadata: &[u8]) -> Result<usize, AptosMailboxError> {
+    let signatures =
+        metadata
+            .get(MULTISIG_SIGNATURES_OFFSET..)
+            .ok_or(AptosMailboxError::MetadataTooShort(
+                metadata.len(),
+                MULTISIG_SIGNATURES_OFFSET,
+            ))?;
+    if signatures.len() % MULTISIG_SIGNATURE_LENGTH != 0 {
+        return Err(AptosMailboxError::MalformedSignatures(
+            signatures.len(),
+            MULTISIG_SIGNATURE_LENGTH,
+        ));
+    }
+    match signatures.len() / MULTISIG_SIGNATURE_LENGTH {
+        0 => Err(AptosMailboxError::MissingSignatures),
+        count => Ok(count),
+    }
+}
+
+/// `vm_status` of a transaction that ran out of gas
+const OUT_OF_GAS_VM_STATUS: &str = "Out of gas";
+
+/// Gas units used by a `process` simulated with `max_gas_amount`. Running out
+/// of gas means processing needs more than the configured cap.
+fn simulated_gas_used(info: &TransactionInfo, max_gas_amount: u64) -> ChainResult<u64> {
+    if !info.success && info.vm_status == OUT_OF_GAS_VM_STATUS {
+        return Err(AptosMailboxError::GasBudgetExceeded(max_gas_amount).into());
+    }
+    utils::check_simulation_status(info.success, &info.vm_status)
+        .map_err(|e| ChainCommunicationError::from_other(AptosSimulationError(e)))?;
+    Ok(info.gas_used.0)
+}
+
+/// Permits for `max_concurrent_submissions` submissions in flight for each
+/// of `payer_count` keys, at least one key's worth for read-only mailboxes
+fn submission_permits(conf: &ConnectionConf, payer_count: usize) -> Semaphore {
+    Semaphore::new(conf.max_concurrent_submissions as usize * payer_count.max(1))
+}
+
+/// Outcome reported for a message found to be delivered before submission,
+/// pointing at the transaction that delivered it. No transaction is sent, so
+/// no gas is used.
+fn already_delivered_outcome(transaction_id: H512) -> TxOutcome {
+    TxOutcome {
+        transaction_id,
+        executed: true,
+        gas_used: U256::zero(),
+        gas_price: U256::zero(),
+    }
+}
+
+/// Run `check` every `poll` until it returns true or `timeout` elapses
+async fn poll_until<F, Fut>(mut check: F, timeout: Duration, poll: Duration) -> ChainResult<bool>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ChainResult<bool>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        if check().await? {
+            return Ok(true);
+        }
+        if Instant::now() + poll > deadline {
+            return Ok(false);
+        }
+        sleep(poll).await;
+    }
+}
+
+/// Run `fut`, failing with a retryable `TransactionTimeout` if it doesn't
+/// finish within `deadline`
+async fn with_deadline<T>(
+    deadline: Duration,
+    fut: impl Future<Output = ChainResult<T>>,
+) -> ChainResult<T> {
+    timeout(deadline, fut)
+        .await
+        .map_err(|_| ChainCommunicationError::TransactionTimeout())?
+}
+
+/// Build the `handle_message` arguments matching the recipient's parameter types.
+/// A leading signer parameter is supplied by the transaction sender, not passed.
+fn build_handle_message_args(
+    params: &[String],
+    encoded_message: &[u8],
+    metadata: &[u8],
+) -> Result<Vec<Vec<u8>>, AptosMailboxError> {
+    let params = match params.split_first() {
+        Some((first, rest)) if first == "&signer" || first == "signer" => rest,
+        _ => params,
+    };
+    match params {
+        [message_param, metadata_param]
+            if message_param == "vector<u8>" && metadata_param == "vector<u8>" =>
+        {
+            Ok(vec![
+                bcs::to_bytes(encoded_message).unwrap(),
+                bcs::to_bytes(metadata).unwrap(),
+            ])
+        }
+        _ => Err(AptosMailboxError::UnsupportedHandleMessageParams(
+            params.join(", "),
+        )),
+    }
+}
+
+/// The index of the latest checkpoint of an outbox holding `count` messages
+fn checkpoint_index(count: usize) -> Result<u32, AptosMailboxError> {
+    let count: u32 = count
+        .try_into()
+        .map_err(|_| AptosMailboxError::OutboxCountOverflow(count))?;
+    count.checked_sub(1).ok_or(AptosMailboxError::EmptyOutbox)
+}
+
+/// The router reports unregistered recipients with an empty module name.
+/// The name is returned as the bytes of the identifier, not hex.
+fn registered_module_name(
+    recipient: &AccountAddress,
+    module_name: Vec<u8>,
+) -> Result<Identifier, AptosMailboxError> {
+    if module_name.is_empty() {
+        return Err(AptosMailboxError::UnregisteredRecipient(*recipient));
+    }
+    Identifier::from_utf8(module_name.clone()).map_err(|_| {
+        AptosMailboxError::InvalidModuleName(
+            *recipient,
+            String::from_utf8_lossy(&module_name).into_owned(),
+        )
+    })
+}
+
+/// The id of the message emitted in a dispatch event
+fn dispatched_message_id(event: &DispatchEventData) -> ChainResult<H256> {
+    convert_hex_string_to_h256(&event.message_id)
+        .map_err(|_| ChainCommunicationError::from_other_str("Invalid dispatched message id"))
+}
+
+/// Decode indexed dispatch events, skipping messages whose body is larger than
+/// `max_body_size` bytes so a single oversized event can't exhaust memory
+fn decode_dispatch_events(
+    events: Vec<(DispatchEventData, LogMeta)>,
+    max_body_size: u32,
+) -> ChainResult<Vec<(HyperlaneMessage, LogMeta)>> {
+    let mut messages = Vec::with_capacity(events.len());
+    for (event, meta) in events {
+        if event.body_exceeds(max_body_size) {
+            warn!(
+                message_id = %event.message_id,
+                max_body_size,
+                "Skipping dispatched message with oversized body"
+            );
+            continue;
+        }
+        messages.push((event.to_message(max_body_size)?, meta));
+    }
+    Ok(messages)
+}
+
+/// Maximum number of `delivered` queries in flight in `filter_undelivered`
+const MAX_CONCURRENT_DELIVERED_QUERIES: usize = 16;
+
+/// Query `delivered` for each of `ids` with bounded concurrency and keep the
+/// undelivered ones, preserving their order
+async fn filter_undelivered_with<F, Fut>(ids: Vec<H256>, delivered: F) -> ChainResult<Vec<H256>>
+where
+    F: Fn(H256) -> Fut,
+    Fut: Future<Output = ChainResult<bool>>,
+{
+    let mut flags = vec![false; ids.len()];
+    {
+        let mut results = stream::iter(ids.iter().enumerate())
+            .map(|(index, id)| delivered(*id).map_ok(move |flag| (index, flag)))
+            .buffer_unordered(MAX_CONCURRENT_DELIVERED_QUERIES);
+        while let Some((index, flag)) = results.try_next().await? {
+            flags[index] = flag;
+        }
+    }
+    Ok(retain_undelivered(ids, &flags))
+}
+
+/// Keep the ids whose corresponding `delivered` flag is false
+fn retain_undelivered(ids: Vec<H256>, delivered: &[bool]) -> Vec<H256> {
+    ids.into_iter()
+        .zip(delivered)
+        .filter_map(|(id, delivered)| (!delivered).then_some(id))
+        .collect()
+}
+
+/// Proof that a message was delivered by a `process` transaction, compact
+/// enough for the relayer to persist for auditing and reprocessing decisions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryReceipt {
+    /// id of the delivered message
+    pub message_id: H256,
+    /// hash of the `process` transaction
+    pub transaction_id: H512,
+    /// ledger version the transaction was committed at
+    pub version: u64,
+}
+
+impl DeliveryReceipt {
+    /// Build the receipt for `message_id` from a committed transaction, or
+    /// `None` if the transaction aborted and so didn't deliver the message
+    pub fn from_transaction_info(message_id: H256, info: &TransactionInfo) -> Option<Self> {
+        if !info.success {
+            return None;
+        }
+        let tx_hash = convert_hex_string_to_h256(&info.hash.to_string()).ok()?;
+        Some(Self {
+            message_id,
+            transaction_id: H512::from(tx_hash),
+            version: info.version.0,
+        })
+    }
+}
+
 /// A reference to a Mailbox contract on some Aptos chain
 pub struct AptosMailbox {
     domain: HyperlaneDomain,
-    payer: Option<Keypair>,
+    conf: ConnectionConf,
+    payers: Vec<Keypair>,
+    next_payer: AtomicUsize,
     aptos_client: AptosClient,
     package_address: AccountAddress,
+    submission_permits: Semaphore,
+    gas_paymaster: Option<AptosInterchainGasPaymasterIndexer>,
+    recipient_module_names: RwLock<HashMap<AccountAddress, Identifier>>,
+    handle_message_params: RwLock<HashMap<AccountAddress, Vec<String>>>,
 }
 
 impl AptosMailbox {
@@ -63,20 +388,442 @@ impl AptosMailbox {
         locator: ContractLocator,
         payer: Option<Keypair>,
     ) -> ChainResult<Self> {
-        let domain = locator.domain.id();
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
-        let aptos_client = AptosClient::new(conf.url.to_string());
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(conf, locator, payer, aptos_client)
+    }
+
+    /// Create a mailbox sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> ChainResult<Self> {
+        Self::from_client(conf, locator, payer, aptos_client)
+    }
+
+    fn from_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> ChainResult<Self> {
+        let package_address = utils::h256_to_account_address(&locator.address);
 
         Ok(AptosMailbox {
             domain: locator.domain.clone(),
-            payer,
+            conf: conf.clone(),
+            payers: payer.into_iter().collect(),
+            next_payer: AtomicUsize::new(0),
             package_address,
             aptos_client,
+            submission_permits: submission_permits(conf, 1),
+            gas_paymaster: None,
+            recipient_module_names: Default::default(),
+            handle_message_params: Default::default(),
         })
     }
 
-    async fn fetch_module_name(&self, package_addy: &AccountAddress) -> ChainResult<Vec<u8>> {
+    /// Create a mailbox for monitoring deployments that hold no signing keys.
+    /// View functions and indexing work as usual, while `process` and other
+    /// submissions fail with `SignerUnavailable`.
+    pub fn read_only(conf: &ConnectionConf, locator: ContractLocator) -> ChainResult<Self> {
+        Self::new(conf, locator, None)
+    }
+
+    /// Whether this mailbox has no key to submit transactions with
+    pub fn is_read_only(&self) -> bool {
+        self.payers.is_empty()
+    }
+
+    /// Record metrics for the requests this mailbox makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self
+            .aptos_client
+            .with_metrics(metrics.clone(), &self.domain);
+        self.gas_paymaster = self.gas_paymaster.map(|igp| igp.with_rpc_metrics(metrics));
+        self
+    }
+
+    /// Check payments to the gas paymaster at `igp_address` before processing
+    /// messages when `enforce_gas_payment` is set
+    pub fn with_gas_paymaster(mut self, igp_address: H256) -> Self {
+        self.gas_paymaster = Some(AptosInterchainGasPaymasterIndexer::from_client(
+            ContractLocator {
+                domain: &self.domain,
+                address: igp_address,
+            },
+            self.aptos_client.clone(),
+        ));
+        self
+    }
+
+    /// Fail with `Underpaid` unless the payments for `message` cover the
+    /// quote for the gas they paid for
+    async fn check_gas_payment(&self, message: &HyperlaneMessage) -> ChainResult<()> {
+        let gas_paymaster = self
+            .gas_paymaster
+            .as_ref()
+            .ok_or(AptosMailboxError::MissingGasPaymaster)?;
+        if !gas_paymaster
+            .is_sufficiently_paid(message.id(), message.destination)
+            .await?
+        {
+            return Err(AptosMailboxError::Underpaid(message.id()).into());
+        }
+        Ok(())
+    }
+
+    /// Hash of the transaction that delivered `message_id`, found among the
+    /// mailbox's process events
+    async fn delivery_transaction(&self, message_id: H256) -> ChainResult<H512> {
+        let events = utils::get_all_account_events(
+            &self.aptos_client,
+            self.package_address,
+            &format!(
+                "{}::mailbox::MailBoxState",
+                self.package_address.to_hex_literal()
+            ),
+            "process_events",
+        )
+        .await?;
+        for event in events.into_iter().rev() {
+            let data = MsgProcessEventData::try_from(event)?;
+            if utils::convert_hex_string_to_h256(&data.message_id).ok() == Some(message_id) {
+                return utils::event_transaction_id(&data);
+            }
+        }
+        Err(AptosMailboxError::MissingProcessEvent(message_id).into())
+    }
+
+    /// Returns whether the recipient's ISM would accept `message` with the
+    /// given `metadata`, by dry running the ISM verification.
+    #[instrument(err, ret, skip(self))]
+    pub async fn can_process(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+    ) -> ChainResult<bool> {
+        let ism_address = self.recipient_ism(message.recipient).await?;
+        let ism = AptosInterchainSecurityModule::from_client(
+            ContractLocator {
+                domain: &self.domain,
+                address: ism_address,
+            },
+            None,
+            self.aptos_client.clone(),
+        );
+        Ok(ism.dry_run_verify(message, metadata).await?.is_some())
+    }
+
+    /// Rotate submissions across `payers` in addition to the configured
+    /// signer, spreading gas usage over several relayer keys. Each key allows
+    /// another `max_concurrent_submissions` submissions in flight.
+    pub fn with_additional_payers(mut self, payers: impl IntoIterator<Item = Keypair>) -> Self {
+        self.payers.extend(payers);
+        self.submission_permits = submission_permits(&self.conf, self.payers.len());
+        self
+    }
+
+    /// The keypair used to sign the next transaction, taking the configured
+    /// keys in turn. Only submissions need one; view functions are called
+    /// without a sender.
+    fn next_payer(&self) -> ChainResult<&Keypair> {
+        if self.payers.is_empty() {
+            return Err(ChainCommunicationError::SignerUnavailable);
+        }
+        let index = self.next_payer.fetch_add(1, Ordering::Relaxed) % self.payers.len();
+        Ok(&self.payers[index])
+    }
+
+    /// The keypair the next transaction will be signed with, without taking
+    /// its turn, for simulations that submit nothing
+    fn upcoming_payer(&self) -> ChainResult<&Keypair> {
+        if self.payers.is_empty() {
+            return Err(ChainCommunicationError::SignerUnavailable);
+        }
+        let index = self.next_payer.load(Ordering::Relaxed) % self.payers.len();
+        Ok(&self.payers[index])
+    }
+
+    /// Like `Mailbox::latest_checkpoint`, but returns `None` instead of an
+    /// error while nothing has been dispatched yet.
+    #[instrument(err, ret, skip(self))]
+    pub async fn latest_checkpoint_if_any(
+        &self,
+        lag: Option<NonZeroU64>,
+    ) -> ChainResult<Option<Checkpoint>> {
+        let tree = self.tree(lag).await?;
+        match checkpoint_index(tree.count()) {
+            Ok(index) => Ok(Some(Checkpoint {
+                mailbox_address: utils::account_address_to_h256(&self.package_address),
+                mailbox_domain: self.domain.id(),
+                root: tree.root(),
+                index,
+            })),
+            Err(AptosMailboxError::EmptyOutbox) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the owner of the mailbox, the only account allowed to change
+    /// its configuration
+    #[instrument(err, ret, skip(self))]
+    pub async fn owner(&self) -> ChainResult<H256> {
+        utils::send_view_request_decoded(
+            &self.aptos_client,
+            self.package_address.to_hex_literal(),
+            "mailbox".to_string(),
+            "owner".to_string(),
+            vec![],
+            vec![],
+        )
+        .await
+    }
+
+    /// Returns the id of the most recently dispatched message, so operators can
+    /// confirm the mailbox is actively dispatching.
+    pub async fn latest_dispatched_id(&self) -> ChainResult<H256> {
+        // every dispatch inserts one leaf and emits one event, so the latest
+        // event's sequence number is the outbox count minus one
+        let count = self.count(None).await?;
+        let sequence_number = checkpoint_index(count as usize)?;
+        let events = self
+            .aptos_client
+            .instrumented(
+                "get_account_events",
+                self.aptos_client.get_account_events(
+                    self.package_address,
+                    &format!(
+                        "{}::mailbox::MailBoxState",
+                        self.package_address.to_hex_literal()
+                    ),
+                    "dispatch_events",
+                    Some(sequence_number.into()),
+                    Some(1),
+                ),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        let event = events
+            .into_iter()
+            .next()
+            .ok_or_else(|| ChainCommunicationError::from_other_str("Dispatch event not found"))?;
+        dispatched_message_id(&DispatchEventData::try_from(event)?)
+    }
+
+    /// Look up and cache the handler modules of `recipients`, so that
+    /// processing messages to them skips the per-message lookup.
+    pub async fn prefetch_recipients(&self, recipients: &[H256]) -> ChainResult<()> {
+        for recipient in recipients {
+            self.recipient_handle_message_params(&recipient.0.into())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the subset of `ids` that have not been delivered yet,
+    /// preserving their order.
+    #[instrument(err, skip(self))]
+    pub async fn filter_undelivered(&self, ids: Vec<H256>) -> ChainResult<Vec<H256>> {
+        filter_undelivered_with(ids, |id| self.delivered(id)).await
+    }
+
+    /// Poll `delivered` every `poll` until the message with `id` is delivered
+    /// or `timeout` elapses. Returns whether the message was delivered.
+    #[instrument(err, ret, skip(self))]
+    pub async fn wait_for_delivery(
+        &self,
+        id: H256,
+        timeout: Duration,
+        poll: Duration,
+    ) -> ChainResult<bool> {
+        poll_until(|| self.delivered(id), timeout, poll).await
+    }
+
+    /// Returns the module name of `recipient`, using the cache when possible.
+    async fn recipient_module_name(&self, recipient: &AccountAddress) -> ChainResult<Identifier> {
+        if let Some(module_name) = self.recipient_module_names.read().unwrap().get(recipient) {
+            return Ok(module_name.clone());
+        }
+        let module_name = self.fetch_module_name(recipient).await?;
+        self.recipient_module_names
+            .write()
+            .unwrap()
+            .insert(*recipient, module_name.clone());
+        Ok(module_name)
+    }
+
+    /// Returns the parameter types of the recipient's `handle_message`,
+    /// using the cache when possible.
+    async fn recipient_handle_message_params(
+        &self,
+        recipient: &AccountAddress,
+    ) -> ChainResult<Vec<String>> {
+        if let Some(params) = self.handle_message_params.read().unwrap().get(recipient) {
+            return Ok(params.clone());
+        }
+        let module_name = self.recipient_module_name(recipient).await?;
+        let params = self
+            .fetch_handle_message_params(recipient, module_name.as_str())
+            .await?;
+        self.handle_message_params
+            .write()
+            .unwrap()
+            .insert(*recipient, params.clone());
+        Ok(params)
+    }
+
+    async fn fetch_handle_message_params(
+        &self,
+        recipient: &AccountAddress,
+        module_name: &str,
+    ) -> ChainResult<Vec<String>> {
+        let module = self
+            .aptos_client
+            .instrumented(
+                "get_account_module",
+                self.aptos_client
+                    .get_account_module(*recipient, module_name),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner()
+            .try_parse_abi()
+            .map_err(|e| ChainCommunicationError::from_other(AptosModuleAbiError(e)))?;
+        let handle_message = module
+            .abi
+            .and_then(|abi| {
+                abi.exposed_functions
+                    .into_iter()
+                    .find(|function| function.name.to_string() == "handle_message")
+            })
+            .ok_or(AptosMailboxError::MissingHandleMessage)?;
+        Ok(handle_message
+            .params
+            .iter()
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    /// Build the `handle_message` call delivering `encoded_message` to `recipient`
+    async fn handle_message_payload(
+        &self,
+        recipient: &AccountAddress,
+        encoded_message: &[u8],
+        metadata: &[u8],
+    ) -> ChainResult<TransactionPayload> {
+        let params = self.recipient_handle_message_params(recipient).await?;
+        let args = build_handle_message_args(&params, encoded_message, metadata)?;
+        let module_name = self.recipient_module_name(recipient).await?;
+        Ok(TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(*recipient, module_name),
+            ident_str!("handle_message").to_owned(),
+            vec![],
+            args,
+        )))
+    }
+
+    /// Process `message` like [`Mailbox::process`], additionally returning a
+    /// [`DeliveryReceipt`] when this call's transaction delivered the message.
+    /// Fails with a retryable `TransactionTimeout` if the lookups and
+    /// submission together take longer than `process_timeout`.
+    #[instrument(err, ret, skip(self))]
+    pub async fn process_with_receipt(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+        tx_gas_limit: Option<U256>,
+    ) -> ChainResult<(TxOutcome, Option<DeliveryReceipt>)> {
+        with_deadline(
+            self.conf.process_timeout,
+            self.submit_process(message, metadata, tx_gas_limit),
+        )
+        .await
+    }
+
+    async fn submit_process(
+        &self,
+        message: &HyperlaneMessage,
+        metadata: &[u8],
+        tx_gas_limit: Option<U256>,
+    ) -> ChainResult<(TxOutcome, Option<DeliveryReceipt>)> {
+        if self.conf.validate_metadata {
+            validate_multisig_metadata(metadata)?;
+        }
+
+        // get recipient address
+        let recipient: AccountAddress = message.recipient.0.into();
+
+        let mut encoded_message = vec![];
+        message.write_to(&mut encoded_message).unwrap();
+        validate_transaction_size(&encoded_message, metadata, self.conf.max_transaction_size)?;
+
+        if self.is_read_only() {
+            return Err(ChainCommunicationError::SignerUnavailable);
+        }
+
+        if self.conf.skip_delivered_messages && self.delivered(message.id()).await? {
+            info!(message_id = ?message.id(), "Message already delivered, skipping process");
+            let transaction_id = self.delivery_transaction(message.id()).await?;
+            return Ok((already_delivered_outcome(transaction_id), None));
+        }
+
+        if self.conf.enforce_gas_payment {
+            self.check_gas_payment(message).await?;
+        }
+
+        // hold a permit until the transaction is committed so that concurrent
+        // submissions don't reuse the same sequence number
+        let _permit = self
+            .submission_permits
+            .acquire()
+            .await
+            .map_err(ChainCommunicationError::from_other)?;
+
+        // only messages that are actually submitted take a payer's turn
+        let payer = self.next_payer()?;
+        let mut signer_account = convert_keypair_to_aptos_account(&self.aptos_client, payer).await;
+
+        let payload = self
+            .handle_message_payload(&recipient, &encoded_message, metadata)
+            .await?;
+
+        // never allow more than the configured cap, even if the relayer asks for it
+        let max_gas_amount = tx_gas_limit
+            .map(|limit| limit.min(U256::from(self.conf.max_gas_amount)).as_u64())
+            .unwrap_or(self.conf.max_gas_amount);
+        let response = send_aptos_transaction(
+            &self.aptos_client,
+            &mut signer_account,
+            None,
+            payload.clone(),
+            max_gas_amount,
+            self.conf.gas_price_strategy,
+        )
+        .await
+        .map_err(|e| {
+            warn!(error = %e, message_id = ?message.id(), "Failed to submit process transaction");
+            ChainCommunicationError::from_other(utils::AptosMoveCallError(e))
+        })?;
+
+        // fetch transaction information from the response
+        let info = utils::transaction_info(&response)?;
+        if !info.success {
+            warn!(
+                message_id = ?message.id(),
+                vm_status = %AbortCodeDescriptions::default().describe_vm_status(&info.vm_status),
+                "Process transaction aborted"
+            );
+        }
+        let receipt = DeliveryReceipt::from_transaction_info(message.id(), info);
+        let outcome = utils::transaction_outcome(&response)?;
+        Ok((outcome, receipt))
+    }
+
+    async fn fetch_module_name(&self, package_addy: &AccountAddress) -> ChainResult<Identifier> {
         let view_response = utils::send_view_request(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
@@ -87,15 +834,15 @@ impl AptosMailbox {
         )
         .await?;
 
-        let module_name = serde_json::from_str::<String>(&view_response[0].to_string()).unwrap();
-        let module_name_bytes = hex::decode(module_name.to_string().trim_start_matches("0x")).unwrap();
-        Ok(module_name_bytes)
+        let module_name =
+            utils::convert_view_value_to_bytes(utils::view_return_at(&view_response, 0)?)?;
+        Ok(registered_module_name(package_addy, module_name)?)
     }
 }
 
 impl HyperlaneContract for AptosMailbox {
     fn address(&self) -> H256 {
-        self.package_address.into_bytes().into()
+        utils::account_address_to_h256(&self.package_address)
     }
 }
 
@@ -105,9 +852,9 @@ impl HyperlaneChain for AptosMailbox {
     }
 
     fn provider(&self) -> Box<dyn HyperlaneProvider> {
-        Box::new(AptosHpProvider::new(
+        Box::new(AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client.path_prefix_string(),
+            self.aptos_client.clone(),
         ))
     }
 }
@@ -122,7 +869,7 @@ impl std::fmt::Debug for AptosMailbox {
 impl Mailbox for AptosMailbox {
     #[instrument(err, ret, skip(self))]
     async fn count(&self, _maybe_lag: Option<NonZeroU64>) -> ChainResult<u32> {
-        let view_response = utils::send_view_request(
+        utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "mailbox".to_string(),
@@ -130,14 +877,12 @@ impl Mailbox for AptosMailbox {
             vec![],
             vec![],
         )
-        .await?;
-        let view_result = serde_json::from_str::<u32>(&view_response[0].to_string()).unwrap();
-        Ok(view_result)
+        .await
     }
 
     #[instrument(err, ret, skip(self))]
     async fn delivered(&self, id: H256) -> ChainResult<bool> {
-        let view_response = utils::send_view_request(
+        utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "mailbox".to_string(),
@@ -145,14 +890,12 @@ impl Mailbox for AptosMailbox {
             vec![],
             vec![serde_json::json!(hex::encode(id.as_bytes()))],
         )
-        .await?;
-        let view_result = serde_json::from_str::<bool>(&view_response[0].to_string()).unwrap();
-        Ok(view_result)
+        .await
     }
 
     #[instrument(err, ret, skip(self))]
     async fn tree(&self, lag: Option<NonZeroU64>) -> ChainResult<IncrementalMerkle> {
-        let view_response = utils::send_view_request(
+        utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "mailbox".to_string(),
@@ -160,31 +903,18 @@ impl Mailbox for AptosMailbox {
             vec![],
             vec![],
         )
-        .await?;
-        let view_result =
-            serde_json::from_str::<MoveMerkleTree>(&view_response[0].to_string()).unwrap();
-        Ok(view_result.into())
+        .await
     }
 
     #[instrument(err, ret, skip(self))]
     async fn latest_checkpoint(&self, lag: Option<NonZeroU64>) -> ChainResult<Checkpoint> {
         let tree = self.tree(lag).await?;
-
-        let root = tree.root();
-        let count: u32 = tree
-            .count()
-            .try_into()
-            .map_err(ChainCommunicationError::from_other)?;
-        let index = count.checked_sub(1).ok_or_else(|| {
-            ChainCommunicationError::from_contract_error_str(
-                "Outbox is empty, cannot compute checkpoint",
-            )
-        })?;
+        let index = checkpoint_index(tree.count())?;
 
         let checkpoint = Checkpoint {
-            mailbox_address: H256::from_str(&self.package_address.to_hex()).unwrap(),
+            mailbox_address: utils::account_address_to_h256(&self.package_address),
             mailbox_domain: self.domain.id(),
-            root,
+            root: tree.root(),
             index,
         };
         Ok(checkpoint)
@@ -192,7 +922,7 @@ impl Mailbox for AptosMailbox {
 
     #[instrument(err, ret, skip(self))]
     async fn default_ism(&self) -> ChainResult<H256> {
-        let view_response = utils::send_view_request(
+        utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "mailbox".to_string(),
@@ -200,16 +930,13 @@ impl Mailbox for AptosMailbox {
             vec![],
             vec![],
         )
-        .await?;
-
-        let ism_address = serde_json::from_str::<String>(&view_response[0].to_string()).unwrap();
-
-        Ok(convert_hex_string_to_h256(&ism_address).unwrap())
+        .await
     }
 
     #[instrument(err, ret, skip(self))]
     async fn recipient_ism(&self, recipient: H256) -> ChainResult<H256> {
-        // !TODO
+        // Aptos recipients can't configure a custom ISM, so every message is
+        // verified by the mailbox's default ISM
         self.default_ism().await
     }
 
@@ -218,55 +945,11 @@ impl Mailbox for AptosMailbox {
         &self,
         message: &HyperlaneMessage,
         metadata: &[u8],
-        _tx_gas_limit: Option<U256>,
+        tx_gas_limit: Option<U256>,
     ) -> ChainResult<TxOutcome> {
-        // get recipient address
-        let recipient: AccountAddress = message.recipient.0.into();
-
-        let mut encoded_message = vec![];
-        message.write_to(&mut encoded_message).unwrap();
-
-        let payer = self
-            .payer
-            .as_ref()
-            .ok_or_else(|| ChainCommunicationError::SignerUnavailable)?;
-
-        let mut signer_account = convert_keypair_to_aptos_account(&self.aptos_client, payer).await;
-
-        let recipient_module_name = self.fetch_module_name(&recipient).await.unwrap();
-        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
-            ModuleId::new(
-                recipient,
-                Identifier::from_utf8(recipient_module_name).unwrap(),
-            ),
-            ident_str!("handle_message").to_owned(),
-            vec![],
-            vec![
-                bcs::to_bytes(&encoded_message).unwrap(),
-                bcs::to_bytes(&metadata.to_vec()).unwrap(),
-            ],
-        ));
-
-        let response =
-            send_aptos_transaction(&self.aptos_client, &mut signer_account, payload.clone())
-                .await
-                .map_err(|e| {
-                    println!("tx error {}", e.to_string());
-                    ChainCommunicationError::TransactionTimeout()
-                })?;
-
-        // fetch transaction information from the response
-        let tx_hash =
-            convert_hex_string_to_h256(&response.transaction_info().unwrap().hash.to_string())
-                .unwrap();
-        let has_success = response.success();
-        let gas_used = response.transaction_info().unwrap().gas_used;
-        Ok(TxOutcome {
-            transaction_id: H512::from(tx_hash),
-            executed: has_success,
-            gas_price: U256::from(GAS_UNIT_PRICE),
-            gas_used: U256::from(gas_used.0),
-        })
+        self.process_with_receipt(message, metadata, tx_gas_limit)
+            .await
+            .map(|(outcome, _)| outcome)
     }
 
     #[instrument(err, ret, skip(self))]
@@ -275,43 +958,45 @@ impl Mailbox for AptosMailbox {
         message: &HyperlaneMessage,
         metadata: &[u8],
     ) -> ChainResult<TxCostEstimate> {
+        if self.conf.validate_metadata {
+            validate_multisig_metadata(metadata)?;
+        }
+
         let recipient: AccountAddress = message.recipient.0.into();
 
         let mut encoded_message = vec![];
         message.write_to(&mut encoded_message).unwrap();
+        validate_transaction_size(&encoded_message, metadata, self.conf.max_transaction_size)?;
 
-        let payer = self
-            .payer
-            .as_ref()
-            .ok_or_else(|| ChainCommunicationError::SignerUnavailable)?;
+        let payer = self.upcoming_payer()?;
 
         let mut signer_account = convert_keypair_to_aptos_account(&self.aptos_client, payer).await;
-        let recipient_module_name = self.fetch_module_name(&recipient).await.unwrap();
-        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
-            ModuleId::new(
-                recipient,
-                Identifier::from_utf8(recipient_module_name).unwrap(),
-            ),
-            ident_str!("handle_message").to_owned(),
-            vec![],
-            vec![
-                bcs::to_bytes(&encoded_message).unwrap(),
-                bcs::to_bytes(&metadata.to_vec()).unwrap(),
-            ],
-        ));
+        let payload = self
+            .handle_message_payload(&recipient, &encoded_message, metadata)
+            .await?;
 
-        let response =
-            simulate_aptos_transaction(&self.aptos_client, &mut signer_account, payload.clone())
-                .await
-                .map_err(|e| {
-                    println!("tx error {}", e.to_string());
-                    ChainCommunicationError::TransactionTimeout()
-                })
-                .unwrap();
+        // Simulate at the price the estimate reports, so both come from a
+        // single node estimate
+        let gas_unit_price =
+            utils::get_gas_unit_price(&self.aptos_client, self.conf.gas_price_strategy).await?;
+        let response = utils::simulate_aptos_transaction_unchecked(
+            &self.aptos_client,
+            &mut signer_account,
+            payload.clone(),
+            self.conf.max_gas_amount,
+            gas_unit_price,
+        )
+        .await
+        .map_err(|e| ChainCommunicationError::from_other(AptosSimulationError(e)))?;
+        let gas_used = simulated_gas_used(&response, self.conf.max_gas_amount)?;
 
+        // `gas_used` of a simulation is already denominated in gas units. It
+        // also covers ISM verification: the recipient's `handle_message` calls
+        // `mailbox::handle_message`, which runs `multisig_ism::verify` in the
+        // same transaction, so there is no separate verification cost to add.
         Ok(TxCostEstimate {
-            gas_limit: U256::from(response.gas_used.0),
-            gas_price: U256::from(GAS_UNIT_PRICE),
+            gas_limit: U256::from(gas_used),
+            gas_price: U256::from(gas_unit_price),
             l2_gas_limit: None,
         })
     }
@@ -327,31 +1012,52 @@ pub struct AptosMailboxIndexer {
     mailbox: AptosMailbox,
     aptos_client: AptosClient,
     package_address: AccountAddress,
+    max_message_body_size: u32,
 }
 
 impl AptosMailboxIndexer {
     pub fn new(conf: &ConnectionConf, locator: ContractLocator) -> ChainResult<Self> {
-        let aptos_client = AptosClient::new(conf.url.to_string());
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
-        let mailbox = AptosMailbox::new(conf, locator, None)?;
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(conf, locator, aptos_client)
+    }
+
+    /// Create a mailbox indexer sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        aptos_client: AptosClient,
+    ) -> ChainResult<Self> {
+        Self::from_client(conf, locator, aptos_client)
+    }
+
+    fn from_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        aptos_client: AptosClient,
+    ) -> ChainResult<Self> {
+        let package_address = utils::h256_to_account_address(&locator.address);
+        let mailbox = AptosMailbox::from_client(conf, locator, None, aptos_client.clone())?;
 
         Ok(Self {
             mailbox,
             aptos_client,
             package_address,
+            max_message_body_size: conf.max_message_body_size,
         })
     }
 
-    async fn get_finalized_block_number(&self) -> ChainResult<u32> {
-        let chain_state = self
+    /// Record metrics for the requests this indexer makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self
             .aptos_client
-            .get_ledger_information()
-            .await
-            .map_err(ChainCommunicationError::from_other)
-            .unwrap()
-            .into_inner();
-        Ok(chain_state.block_height as u32)
+            .with_metrics(metrics.clone(), &self.mailbox.domain);
+        self.mailbox = self.mailbox.with_rpc_metrics(metrics);
+        self
+    }
+
+    async fn get_finalized_block_number(&self) -> ChainResult<u32> {
+        utils::get_finalized_block_number(&self.aptos_client).await
     }
 }
 
@@ -371,7 +1077,7 @@ impl Indexer<HyperlaneMessage> for AptosMailboxIndexer {
         &self,
         range: RangeInclusive<u32>,
     ) -> ChainResult<Vec<(HyperlaneMessage, LogMeta)>> {
-        get_filtered_events::<HyperlaneMessage, DispatchEventData>(
+        let events = utils::get_filtered_event_data::<DispatchEventData>(
             &self.aptos_client,
             self.package_address,
             &format!(
@@ -381,7 +1087,8 @@ impl Indexer<HyperlaneMessage> for AptosMailboxIndexer {
             "dispatch_events",
             range,
         )
-        .await
+        .await?;
+        decode_dispatch_events(events, self.max_message_body_size)
     }
 
     async fn get_finalized_block_number(&self) -> ChainResult<u32> {
@@ -430,3 +1137,904 @@ impl HyperlaneAbi for AptosMailboxAbi {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use aptos_sdk::{
+        move_types::identifier::Identifier, rest_client::aptos_api_types::TransactionInfo,
+        types::account_address::AccountAddress,
+    };
+    use hyperlane_core::{
+        ChainCommunicationError, ChainResult, ContractLocator, Encode, HyperlaneChain,
+        HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
+        KnownHyperlaneDomain, LogMeta, Mailbox, TxOutcome, H256, H512, U256,
+    };
+
+    use solana_sdk::signature::Keypair;
+
+    use super::{
+        already_delivered_outcome, build_handle_message_args, checkpoint_index,
+        decode_dispatch_events, dispatched_message_id, filter_undelivered_with, poll_until,
+        registered_module_name, retain_undelivered, simulated_gas_used, validate_multisig_metadata,
+        validate_transaction_size, with_deadline, AptosMailbox, AptosMailboxError, DeliveryReceipt,
+    };
+    use crate::mock::MockAptosRpc;
+    use crate::{
+        utils, AptosClient, AptosRpcMetrics, ConnectionConf, DispatchEventData,
+        APTOS_RPC_REQUEST_COUNT_LABELS, APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS,
+        DEFAULT_MAX_TRANSACTION_SIZE, PROCESS_TRANSACTION_OVERHEAD,
+    };
+
+    fn test_mailbox(max_concurrent_submissions: u32) -> AptosMailbox {
+        let conf = ConnectionConf {
+            max_concurrent_submissions,
+            ..ConnectionConf::test_default()
+        };
+        AptosMailbox::read_only(
+            &conf,
+            ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+                // Address doesn't matter because no requests are made
+                address: H256::zero(),
+            },
+        )
+        .unwrap()
+    }
+
+    fn mocked_mailbox(rpc: Arc<MockAptosRpc>) -> AptosMailbox {
+        mocked_mailbox_with(ConnectionConf::test_default(), rpc, None)
+    }
+
+    fn mocked_mailbox_with(
+        conf: ConnectionConf,
+        rpc: Arc<MockAptosRpc>,
+        payer: Option<Keypair>,
+    ) -> AptosMailbox {
+        AptosMailbox::with_client(
+            &conf,
+            ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+                address: H256::repeat_byte(0x11),
+            },
+            payer,
+            AptosClient::from_rpc(rpc),
+        )
+        .unwrap()
+    }
+
+    fn recipient_message() -> HyperlaneMessage {
+        HyperlaneMessage {
+            recipient: H256::repeat_byte(0x22),
+            ..Default::default()
+        }
+    }
+
+    /// Cache the handler lookups of the recipient of `message`, so processing
+    /// it needs no module requests
+    fn cache_recipient(mailbox: &AptosMailbox, message: &HyperlaneMessage) {
+        let address: AccountAddress = message.recipient.0.into();
+        mailbox
+            .recipient_module_names
+            .write()
+            .unwrap()
+            .insert(address, Identifier::new("recipient").unwrap());
+        mailbox.handle_message_params.write().unwrap().insert(
+            address,
+            vec![
+                "&signer".to_string(),
+                "vector<u8>".to_string(),
+                "vector<u8>".to_string(),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_estimate_costs_fetches_the_gas_price_once() {
+        let rpc = Arc::new(
+            MockAptosRpc::default()
+                .with_gas_estimate(150)
+                .with_simulation(simulated_info(true, "Executed successfully", 850)),
+        );
+        let mailbox = mocked_mailbox_with(
+            ConnectionConf::test_default(),
+            rpc.clone(),
+            Some(Keypair::new()),
+        );
+        let message = recipient_message();
+        cache_recipient(&mailbox, &message);
+
+        let estimate = mailbox.process_estimate_costs(&message, &[]).await.unwrap();
+
+        assert_eq!(estimate.gas_price, U256::from(150));
+        assert_eq!(estimate.gas_limit, U256::from(850));
+        assert_eq!(rpc.requests("estimate_gas_price").len(), 1);
+        assert_eq!(rpc.requests("simulate"), vec!["gas_unit_price=150"]);
+    }
+
+    #[tokio::test]
+    async fn test_delivered_queries_the_mailbox_view() {
+        let id = H256::repeat_byte(0xab);
+        for delivered in [true, false] {
+            let rpc = Arc::new(
+                MockAptosRpc::default()
+                    .with_view("mailbox::delivered", vec![serde_json::json!(delivered)]),
+            );
+            let mailbox = mocked_mailbox(rpc.clone());
+
+            assert_eq!(mailbox.delivered(id).await.unwrap(), delivered);
+            let requests = rpc.requests("view");
+            assert_eq!(requests.len(), 1);
+            assert!(requests[0].contains(&hex::encode(id.as_bytes())));
+        }
+    }
+
+    fn gas_payment(message_id: H256, required_payment: u64) -> serde_json::Value {
+        serde_json::json!({
+            "message_id": format!("{:?}", message_id),
+            "gas_amount": "100000",
+            "required_payment": required_payment.to_string(),
+            "block_height": "1",
+            "transaction_hash": "0x01",
+        })
+    }
+
+    /// Process `message`, paid for with two payments of 10 and 15, against an
+    /// IGP quoting `quote` for the gas they paid for
+    async fn process_with_gas_payments(quote: u64) -> (Arc<MockAptosRpc>, ChainResult<TxOutcome>) {
+        let message = recipient_message();
+        let igp = H256::repeat_byte(0x33);
+        let rpc = Arc::new(
+            MockAptosRpc::default()
+                .with_resource(
+                    &format!(
+                        "{}::igps::IgpState",
+                        utils::h256_to_account_address(&igp).to_hex_literal()
+                    ),
+                    serde_json::json!({ "gas_payment_events": { "counter": "3" } }),
+                )
+                .with_events(
+                    "gas_payment_events",
+                    vec![
+                        gas_payment(message.id(), 10),
+                        gas_payment(H256::repeat_byte(0x44), 100),
+                        gas_payment(message.id(), 15),
+                    ],
+                )
+                .with_view(
+                    "igps::quote_gas_payment",
+                    vec![serde_json::json!(quote.to_string())],
+                )
+                .with_gas_estimate(100)
+                .with_submission(simulated_info(true, "Executed successfully", 850)),
+        );
+        let conf = ConnectionConf {
+            enforce_gas_payment: true,
+            ..ConnectionConf::test_default()
+        };
+        let mailbox =
+            mocked_mailbox_with(conf, rpc.clone(), Some(Keypair::new())).with_gas_paymaster(igp);
+        cache_recipient(&mailbox, &message);
+
+        let outcome = mailbox.process(&message, &[], None).await;
+        (rpc, outcome)
+    }
+
+    #[tokio::test]
+    async fn test_process_rejects_underpaid_messages() {
+        let (rpc, outcome) = process_with_gas_payments(30).await;
+
+        let err = outcome.unwrap_err().to_string();
+        assert!(err.contains("is not sufficiently paid for"), "{err}");
+        assert!(rpc.requests("submit_and_wait").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_submits_fully_paid_messages() {
+        let (rpc, outcome) = process_with_gas_payments(25).await;
+
+        assert!(outcome.unwrap().executed);
+        assert_eq!(rpc.requests("submit_and_wait").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_enforcing_gas_payment_requires_a_gas_paymaster() {
+        let conf = ConnectionConf {
+            enforce_gas_payment: true,
+            ..ConnectionConf::test_default()
+        };
+        let rpc = Arc::new(MockAptosRpc::default());
+        let mailbox = mocked_mailbox_with(conf, rpc.clone(), Some(Keypair::new()));
+
+        let err = mailbox
+            .process(&recipient_message(), &[], None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ChainCommunicationError::from(AptosMailboxError::MissingGasPaymaster).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delivered_fails_when_view_fails() {
+        let mailbox = mocked_mailbox(Arc::new(MockAptosRpc::default()));
+
+        assert!(mailbox.delivered(H256::zero()).await.is_err());
+    }
+
+    fn simulated_info(success: bool, vm_status: &str, gas_used: u64) -> TransactionInfo {
+        serde_json::from_value(serde_json::json!({
+            "version": "1234567",
+            "hash": format!("0x{}", "ab".repeat(32)),
+            "state_change_hash": format!("0x{}", "11".repeat(32)),
+            "event_root_hash": format!("0x{}", "22".repeat(32)),
+            "gas_used": gas_used.to_string(),
+            "success": success,
+            "vm_status": vm_status,
+            "accumulator_root_hash": format!("0x{}", "33".repeat(32)),
+            "changes": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_simulation_out_of_gas_exceeds_gas_budget() {
+        let info = simulated_info(true, "Executed successfully", 850);
+        assert_eq!(simulated_gas_used(&info, 1000).unwrap(), 850);
+
+        let info = simulated_info(false, "Out of gas", 1000);
+        let err = simulated_gas_used(&info, 1000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            AptosMailboxError::GasBudgetExceeded(1000).to_string()
+        );
+
+        // other failures are not attributed to the gas budget
+        let info = simulated_info(false, "Move abort in 0xa11ce::mailbox: 0x4", 500);
+        let err = simulated_gas_used(&info, 1000).unwrap_err();
+        assert!(err.to_string().contains("message was already delivered"));
+    }
+
+    #[test]
+    fn test_submission_permits_cap_concurrent_submissions() {
+        let mailbox = test_mailbox(2);
+
+        let first = mailbox.submission_permits.try_acquire().unwrap();
+        let _second = mailbox.submission_permits.try_acquire().unwrap();
+        assert!(mailbox.submission_permits.try_acquire().is_err());
+
+        drop(first);
+        assert!(mailbox.submission_permits.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_submission_permits_scale_with_payers() {
+        let mailbox = test_mailbox(2).with_additional_payers([Keypair::new(), Keypair::new()]);
+
+        let _permits: Vec<_> = (0..4)
+            .map(|_| mailbox.submission_permits.try_acquire().unwrap())
+            .collect();
+        assert!(mailbox.submission_permits.try_acquire().is_err());
+    }
+
+    /// A mailbox rotating over two payers, returning the first one
+    fn two_payer_mailbox(conf: ConnectionConf, rpc: Arc<MockAptosRpc>) -> (AptosMailbox, Keypair) {
+        let first = Keypair::new();
+        let mailbox = mocked_mailbox_with(
+            conf,
+            rpc,
+            Some(Keypair::from_bytes(&first.to_bytes()).unwrap()),
+        )
+        .with_additional_payers([Keypair::new()]);
+        (mailbox, first)
+    }
+
+    #[tokio::test]
+    async fn test_estimates_do_not_take_a_payer_turn() {
+        let rpc = Arc::new(
+            MockAptosRpc::default()
+                .with_gas_estimate(100)
+                .with_simulation(simulated_info(true, "Executed successfully", 850)),
+        );
+        let (mailbox, first) = two_payer_mailbox(ConnectionConf::test_default(), rpc);
+        let message = recipient_message();
+        cache_recipient(&mailbox, &message);
+
+        for _ in 0..3 {
+            mailbox.process_estimate_costs(&message, &[]).await.unwrap();
+        }
+
+        assert_eq!(mailbox.next_payer().unwrap().to_bytes(), first.to_bytes());
+    }
+
+    fn process_event(message_id: H256, transaction_hash: H256) -> serde_json::Value {
+        serde_json::json!({
+            "message_id": format!("{:?}", message_id),
+            "origin_domain": 1,
+            "sender": "0x01",
+            "recipient": "0x22",
+            "block_height": "1",
+            "transaction_hash": format!("{:?}", transaction_hash),
+        })
+    }
+
+    /// A mailbox reporting every message as delivered, with `events` emitted
+    /// to its process event handle
+    fn delivered_rpc(events: Vec<serde_json::Value>) -> MockAptosRpc {
+        MockAptosRpc::default()
+            .with_view("mailbox::delivered", vec![serde_json::json!(true)])
+            .with_resource(
+                &format!(
+                    "{}::mailbox::MailBoxState",
+                    utils::h256_to_account_address(&H256::repeat_byte(0x11)).to_hex_literal()
+                ),
+                serde_json::json!({ "process_events": { "counter": events.len().to_string() } }),
+            )
+            .with_events("process_events", events)
+    }
+
+    #[tokio::test]
+    async fn test_already_delivered_messages_do_not_take_a_payer_turn() {
+        let message = recipient_message();
+        let rpc = Arc::new(delivered_rpc(vec![process_event(
+            message.id(),
+            H256::repeat_byte(0xdd),
+        )]));
+        let conf = ConnectionConf {
+            skip_delivered_messages: true,
+            ..ConnectionConf::test_default()
+        };
+        let (mailbox, first) = two_payer_mailbox(conf, rpc.clone());
+
+        mailbox.process(&message, &[], None).await.unwrap();
+
+        assert!(rpc.requests("get_account").is_empty());
+        assert_eq!(mailbox.next_payer().unwrap().to_bytes(), first.to_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_already_delivered_outcome_points_at_the_delivery() {
+        let message = recipient_message();
+        let delivery = H256::repeat_byte(0xdd);
+        let rpc = Arc::new(delivered_rpc(vec![
+            process_event(H256::repeat_byte(0x44), H256::repeat_byte(0xee)),
+            process_event(message.id(), delivery),
+        ]));
+        let conf = ConnectionConf {
+            skip_delivered_messages: true,
+            ..ConnectionConf::test_default()
+        };
+        let mailbox = mocked_mailbox_with(conf, rpc.clone(), Some(Keypair::new()));
+
+        let outcome = mailbox.process(&message, &[], None).await.unwrap();
+
+        assert_eq!(outcome.transaction_id, H512::from(delivery));
+        assert!(outcome.executed);
+        assert!(outcome.gas_used.is_zero());
+        assert!(rpc.requests("submit_and_wait").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_already_delivered_without_process_event_fails() {
+        let message = recipient_message();
+        let rpc = Arc::new(delivered_rpc(vec![process_event(
+            H256::repeat_byte(0x44),
+            H256::repeat_byte(0xee),
+        )]));
+        let conf = ConnectionConf {
+            skip_delivered_messages: true,
+            ..ConnectionConf::test_default()
+        };
+        let mailbox = mocked_mailbox_with(conf, rpc, Some(Keypair::new()));
+
+        let err = mailbox.process(&message, &[], None).await.unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            AptosMailboxError::MissingProcessEvent(message.id()).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_recipient_skips_lookup() {
+        // No node is listening on the test url, so any lookup would fail
+        let mailbox = test_mailbox(1);
+        let recipient = utils::h256_to_account_address(&H256::repeat_byte(1));
+        mailbox
+            .recipient_module_names
+            .write()
+            .unwrap()
+            .insert(recipient, Identifier::new("hello_world").unwrap());
+
+        assert_eq!(
+            mailbox
+                .recipient_module_name(&recipient)
+                .await
+                .unwrap()
+                .as_str(),
+            "hello_world"
+        );
+    }
+
+    #[test]
+    fn test_already_delivered_outcome_sends_nothing() {
+        let delivery = H512::repeat_byte(0xdd);
+        let outcome = already_delivered_outcome(delivery);
+        assert!(outcome.executed);
+        assert_eq!(outcome.transaction_id, delivery);
+        assert!(outcome.gas_used.is_zero());
+        assert!(outcome.gas_price.is_zero());
+    }
+
+    #[tokio::test]
+    async fn test_poll_until_delivered_after_polls() {
+        let mut polls = 0;
+        let delivered = poll_until(
+            || {
+                polls += 1;
+                let delivered = polls == 3;
+                async move { Ok(delivered) }
+            },
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+        assert!(delivered);
+        assert_eq!(polls, 3);
+
+        let delivered = poll_until(
+            || async { Ok(false) },
+            Duration::from_millis(10),
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap();
+        assert!(!delivered);
+    }
+
+    #[test]
+    fn test_build_handle_message_args_matches_signature() {
+        let message = vec![1u8, 2, 3];
+        let metadata = vec![4u8; 200];
+        let expected = vec![
+            bcs::to_bytes(&message).unwrap(),
+            bcs::to_bytes(&metadata).unwrap(),
+        ];
+
+        let params = ["vector<u8>".to_string(), "vector<u8>".to_string()];
+        assert_eq!(
+            build_handle_message_args(&params, &message, &metadata).unwrap(),
+            expected
+        );
+
+        let params = [
+            "&signer".to_string(),
+            "vector<u8>".to_string(),
+            "vector<u8>".to_string(),
+        ];
+        assert_eq!(
+            build_handle_message_args(&params, &message, &metadata).unwrap(),
+            expected
+        );
+
+        let params = ["vector<u8>".to_string(), "u64".to_string()];
+        assert!(matches!(
+            build_handle_message_args(&params, &message, &metadata),
+            Err(AptosMailboxError::UnsupportedHandleMessageParams(params))
+                if params == "vector<u8>, u64"
+        ));
+    }
+
+    #[test]
+    fn test_handle_message_args_round_trip_as_byte_vectors() {
+        let message = vec![1u8, 2, 3];
+        let metadata = vec![4u8; 200];
+        let params = ["vector<u8>".to_string(), "vector<u8>".to_string()];
+
+        let args = build_handle_message_args(&params, &message, &metadata).unwrap();
+        assert_eq!(bcs::from_bytes::<Vec<u8>>(&args[0]).unwrap(), message);
+        assert_eq!(bcs::from_bytes::<Vec<u8>>(&args[1]).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_checkpoint_index_of_empty_outbox_is_typed() {
+        assert!(matches!(
+            checkpoint_index(0),
+            Err(AptosMailboxError::EmptyOutbox)
+        ));
+        assert_eq!(checkpoint_index(5).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_retain_undelivered() {
+        let ids = vec![
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+            H256::repeat_byte(3),
+            H256::repeat_byte(4),
+        ];
+        assert_eq!(
+            retain_undelivered(ids, &[true, false, true, false]),
+            vec![H256::repeat_byte(2), H256::repeat_byte(4)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_undelivered_queries_concurrently_and_keeps_order() {
+        let in_flight = &AtomicUsize::new(0);
+        let max_in_flight = &AtomicUsize::new(0);
+        let ids: Vec<H256> = (1..=4).map(H256::repeat_byte).collect();
+
+        let undelivered = filter_undelivered_with(ids, |id| async move {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            // later ids finish first
+            let byte = id.as_bytes()[0];
+            tokio::time::sleep(Duration::from_millis(10 * (5 - byte as u64))).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(byte % 2 == 1)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            undelivered,
+            vec![H256::repeat_byte(2), H256::repeat_byte(4)]
+        );
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_filter_undelivered_propagates_query_errors() {
+        let result = filter_undelivered_with(vec![H256::zero()], |_| async {
+            Err(ChainCommunicationError::from_other_str("rpc unavailable"))
+        })
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submissions_without_signer_are_signer_unavailable() {
+        let mailbox = test_mailbox(1);
+        let message = HyperlaneMessage::default();
+
+        assert!(matches!(
+            mailbox.process(&message, &[], None).await,
+            Err(ChainCommunicationError::SignerUnavailable)
+        ));
+        assert!(matches!(
+            mailbox.process_estimate_costs(&message, &[]).await,
+            Err(ChainCommunicationError::SignerUnavailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mailbox_rejects_submissions() {
+        let mailbox = test_mailbox(1);
+        assert!(mailbox.is_read_only());
+        assert_eq!(mailbox.address(), H256::zero());
+        assert_eq!(
+            mailbox.domain(),
+            &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1)
+        );
+
+        let message = HyperlaneMessage::default();
+        assert!(matches!(
+            mailbox.process(&message, &[], None).await,
+            Err(ChainCommunicationError::SignerUnavailable)
+        ));
+        assert!(matches!(
+            mailbox.process_with_receipt(&message, &[], None).await,
+            Err(ChainCommunicationError::SignerUnavailable)
+        ));
+
+        let mailbox = mailbox.with_additional_payers([Keypair::new()]);
+        assert!(!mailbox.is_read_only());
+    }
+
+    #[test]
+    fn test_payers_rotate_across_submissions() {
+        let payers = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let mailbox = test_mailbox(1).with_additional_payers(
+            payers
+                .iter()
+                .map(|payer| Keypair::from_bytes(&payer.to_bytes()).unwrap()),
+        );
+
+        let used: Vec<_> = (0..6)
+            .map(|_| mailbox.next_payer().unwrap().to_bytes())
+            .collect();
+        let expected: Vec<_> = payers
+            .iter()
+            .chain(payers.iter())
+            .map(Keypair::to_bytes)
+            .collect();
+        assert_eq!(used, expected);
+    }
+
+    #[test]
+    fn test_validate_transaction_size_counts_message_metadata_and_overhead() {
+        let max = DEFAULT_MAX_TRANSACTION_SIZE;
+        let message = vec![0u8; 200];
+        let fitting = max as usize - PROCESS_TRANSACTION_OVERHEAD - message.len();
+
+        let metadata = vec![0u8; fitting];
+        assert!(validate_transaction_size(&message, &metadata, max).is_ok());
+
+        let metadata = vec![0u8; fitting + 1];
+        let err = validate_transaction_size(&message, &metadata, max).unwrap_err();
+        assert!(matches!(
+            err,
+            AptosMailboxError::TransactionTooLarge(size, limit)
+                if size == max as usize + 1 && limit == max
+        ));
+        assert!(err
+            .to_string()
+            .contains("exceeding the maximum transaction size"));
+
+        // metadata filling the whole limit leaves no room for the rest of the transaction
+        let metadata = vec![0u8; max as usize];
+        assert!(validate_transaction_size(&[], &metadata, max).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_process_rejects_transactions_over_the_configured_size() {
+        let conf = ConnectionConf {
+            max_transaction_size: 2048,
+            ..ConnectionConf::test_default()
+        };
+        let rpc = Arc::new(MockAptosRpc::default());
+        let mailbox = mocked_mailbox_with(conf, rpc.clone(), Some(Keypair::new()));
+
+        let err = mailbox
+            .process(&recipient_message(), &[0u8; 1024], None)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("exceeding the maximum transaction size of 2048 bytes"));
+        assert!(rpc.requests("submit_and_wait").is_empty());
+    }
+
+    #[test]
+    fn test_unregistered_recipient_is_named_in_error() {
+        // An EVM-style 20-byte address left-padded to 32 bytes
+        let recipient = AccountAddress::from_hex_literal(
+            "0x00000000000000000000000071c7656ec7ab88b098defb751b7401b5f6d8976f",
+        )
+        .unwrap();
+        let err = registered_module_name(&recipient, vec![]).unwrap_err();
+        assert!(matches!(err, AptosMailboxError::UnregisteredRecipient(r) if r == recipient));
+        assert!(err.to_string().contains(&recipient.to_string()));
+
+        assert_eq!(
+            registered_module_name(&recipient, b"hello_world".to_vec())
+                .unwrap()
+                .as_str(),
+            "hello_world"
+        );
+    }
+
+    #[test]
+    fn test_registered_module_name_is_an_identifier() {
+        let recipient = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+        // `recipient_module_name` returns the name as a hex encoded vector<u8>
+        let module_name =
+            crate::convert_view_value_to_bytes(&serde_json::json!("0x6d61696c626f78")).unwrap();
+        assert_eq!(
+            registered_module_name(&recipient, module_name)
+                .unwrap()
+                .as_str(),
+            "mailbox"
+        );
+
+        let err = registered_module_name(&recipient, b"mail box".to_vec()).unwrap_err();
+        assert!(matches!(
+            err,
+            AptosMailboxError::InvalidModuleName(r, name) if r == recipient && name == "mail box"
+        ));
+    }
+
+    #[test]
+    fn test_validate_multisig_metadata_counts_signatures() {
+        let metadata = [[1u8; 64].as_slice(), &[2u8; 65], &[3u8; 65]].concat();
+        assert_eq!(validate_multisig_metadata(&metadata).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_validate_multisig_metadata_rejects_truncated_metadata() {
+        assert!(matches!(
+            validate_multisig_metadata(&[0u8; 40]),
+            Err(AptosMailboxError::MetadataTooShort(40, 64))
+        ));
+        assert!(matches!(
+            validate_multisig_metadata(&[0u8; 64 + 65 + 10]),
+            Err(AptosMailboxError::MalformedSignatures(75, 65))
+        ));
+        assert!(matches!(
+            validate_multisig_metadata(&[0u8; 64]),
+            Err(AptosMailboxError::MissingSignatures)
+        ));
+    }
+
+    #[test]
+    fn test_delivery_receipt_from_transaction_info() {
+        let hash = "0x3b8b1c9bb8eab2f9c5a9a6cfb5fdb6f2c1ec2f8ab3a2b1c0d9e8f7a6b5c4d3e2";
+        let info = |success: bool| -> TransactionInfo {
+            serde_json::from_value(serde_json::json!({
+                "version": "1234567",
+                "hash": hash,
+                "state_change_hash": format!("0x{}", "11".repeat(32)),
+                "event_root_hash": format!("0x{}", "22".repeat(32)),
+                "gas_used": "850",
+                "success": success,
+                "vm_status": "Executed successfully",
+                "accumulator_root_hash": format!("0x{}", "33".repeat(32)),
+                "changes": [],
+            }))
+            .unwrap()
+        };
+        let message_id = H256::from_low_u64_be(42);
+
+        assert_eq!(
+            DeliveryReceipt::from_transaction_info(message_id, &info(true)),
+            Some(DeliveryReceipt {
+                message_id,
+                transaction_id: H512::from(H256::from_str(&hash[2..]).unwrap()),
+                version: 1234567,
+            })
+        );
+        assert_eq!(
+            DeliveryReceipt::from_transaction_info(message_id, &info(false)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dispatched_message_id() {
+        let event: DispatchEventData = serde_json::from_value(serde_json::json!({
+            "dest_domain": 14411,
+            "message": "0x00",
+            "message_id": "0x89d60a2c1bb5a4fcd3f2b06d23b8a8f58ce2a8c1fec7fb3b70be2ed3ebf3c3c4",
+            "recipient": "0x0000000000000000000000000000000000000000000000000000000000000b0b",
+            "block_height": "1024",
+            "transaction_hash": "0x11",
+            "sender": "0xa11ce",
+        }))
+        .unwrap();
+        assert_eq!(
+            dispatched_message_id(&event).unwrap(),
+            H256::from_str("89d60a2c1bb5a4fcd3f2b06d23b8a8f58ce2a8c1fec7fb3b70be2ed3ebf3c3c4")
+                .unwrap()
+        );
+
+        let malformed = DispatchEventData {
+            message_id: "0xnot-hex".to_owned(),
+            ..event
+        };
+        assert!(dispatched_message_id(&malformed).is_err());
+    }
+
+    #[test]
+    fn test_decode_dispatch_events_skips_oversized_bodies() {
+        let event_for = |message: &HyperlaneMessage| DispatchEventData {
+            dest_domain: message.destination as u64,
+            message: format!("0x{}", hex::encode(message.to_vec())),
+            message_id: format!("{:?}", message.id()),
+            recipient: format!("{:?}", message.recipient),
+            block_height: "1024".to_owned(),
+            transaction_hash: "0x11".to_owned(),
+            sender: format!("{:?}", message.sender),
+        };
+        let meta = |log_index: u64| LogMeta {
+            address: H256::zero(),
+            block_number: 1024,
+            block_hash: H256::zero(),
+            transaction_id: H512::zero(),
+            transaction_index: 0,
+            log_index: U256::from(log_index),
+        };
+        let small = HyperlaneMessage {
+            nonce: 0,
+            body: vec![1; 16],
+            ..Default::default()
+        };
+        let large = HyperlaneMessage {
+            nonce: 1,
+            body: vec![1; 4096],
+            ..Default::default()
+        };
+
+        let decoded = decode_dispatch_events(
+            vec![(event_for(&small), meta(0)), (event_for(&large), meta(1))],
+            1024,
+        )
+        .unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].0.id(), small.id());
+        assert_eq!(decoded[0].1, meta(0));
+
+        let decoded = decode_dispatch_events(vec![(event_for(&large), meta(1))], 4096).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].0.id(), large.id());
+    }
+
+    #[test]
+    fn test_decode_owner() {
+        // `mailbox::owner` returns an address, shortened for special addresses
+        let owner: H256 = crate::decode_view_return(&[serde_json::json!(
+            "0x61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb"
+        )])
+        .unwrap();
+        assert_eq!(
+            owner,
+            H256::from_str("61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb")
+                .unwrap()
+        );
+
+        let owner: H256 = crate::decode_view_return(&[serde_json::json!("0x1")]).unwrap();
+        assert_eq!(owner, H256::from_low_u64_be(1));
+    }
+
+    #[tokio::test]
+    async fn test_process_deadline_times_out_slow_calls() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        };
+        assert!(matches!(
+            with_deadline(Duration::from_millis(10), slow).await,
+            Err(ChainCommunicationError::TransactionTimeout())
+        ));
+
+        assert_eq!(
+            with_deadline(Duration::from_secs(60), async { Ok(7) })
+                .await
+                .unwrap(),
+            7
+        );
+        assert!(matches!(
+            with_deadline::<()>(Duration::from_secs(60), async {
+                Err(ChainCommunicationError::SignerUnavailable)
+            })
+            .await,
+            Err(ChainCommunicationError::SignerUnavailable)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_provider_shares_instrumented_client() {
+        let request_count = prometheus::IntCounterVec::new(
+            prometheus::opts!("request_count", "help"),
+            APTOS_RPC_REQUEST_COUNT_LABELS,
+        )
+        .unwrap();
+        let request_duration_seconds = prometheus::HistogramVec::new(
+            prometheus::histogram_opts!("request_duration_seconds", "help"),
+            APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS,
+        )
+        .unwrap();
+        let mailbox = mocked_mailbox(Arc::new(MockAptosRpc::default())).with_rpc_metrics(
+            AptosRpcMetrics::new(request_count.clone(), request_duration_seconds),
+        );
+
+        mailbox
+            .provider()
+            .get_txn_by_hash(&H256::repeat_byte(0xab))
+            .await
+            .unwrap_err();
+
+        let labels = [
+            mailbox.domain().name(),
+            "get_transaction_by_hash",
+            "failure",
+        ];
+        assert_eq!(request_count.with_label_values(&labels).get(), 1);
+    }
+}
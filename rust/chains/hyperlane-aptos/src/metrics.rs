@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use prometheus::{HistogramVec, IntCounterVec};
+
+/// Expected label names for the request count metric.
+pub const APTOS_RPC_REQUEST_COUNT_LABELS: &[&str] = &["chain", "method", "status"];
+/// Help string for the request count metric.
+pub const APTOS_RPC_REQUEST_COUNT_HELP: &str = "Total number of requests made to the aptos node";
+
+/// Expected label names for the request duration metric.
+pub const APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS: &[&str] = &["chain", "method", "status"];
+/// Help string for the request duration metric.
+pub const APTOS_RPC_REQUEST_DURATION_SECONDS_HELP: &str =
+    "Latency of requests made to the aptos node";
+
+/// Metrics recorded for every request an `AptosClient` makes. The metric vecs
+/// are created and registered externally and must include the labels above.
+/// - `chain`: name of the chain the request was made on.
+/// - `method`: name of the REST client method, e.g. `view`.
+/// - `status`: `success` or `failure` depending on the response.
+#[derive(Clone, Debug)]
+pub struct AptosRpcMetrics {
+    request_count: IntCounterVec,
+    request_duration_seconds: HistogramVec,
+}
+
+impl AptosRpcMetrics {
+    /// Create a new set of aptos rpc metrics
+    pub fn new(request_count: IntCounterVec, request_duration_seconds: HistogramVec) -> Self {
+        Self {
+            request_count,
+            request_duration_seconds,
+        }
+    }
+
+    pub(crate) fn observe(&self, chain: &str, method: &str, success: bool, elapsed: Duration) {
+        let labels = [chain, method, if success { "success" } else { "failure" }];
+        self.request_count.with_label_values(&labels).inc();
+        self.request_duration_seconds
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::rest_client::{
+    aptos_api_types::{
+        Block, GasEstimation, MoveModuleBytecode, Transaction, TransactionInfo, UserTransaction,
+        VersionedEvent, ViewRequest,
+    },
+    error::RestError,
+    Account, Resource, Response, State,
+};
+use aptos_sdk::types::{account_address::AccountAddress, transaction::SignedTransaction};
+use async_trait::async_trait;
+
+use crate::{AptosRpc, EVENTS_PAGE_LIMIT};
+
+/// An `AptosRpc` answering from canned responses instead of a node.
+/// The ledger and every account are always available, with accounts at
+/// sequence number 0. Other requests without a canned response fail.
+#[derive(Default)]
+pub struct MockAptosRpc {
+    views: HashMap<String, Vec<serde_json::Value>>,
+    events: HashMap<String, Vec<serde_json::Value>>,
+    resources: HashMap<String, serde_json::Value>,
+    gas_estimate: Option<u64>,
+    simulation: Option<TransactionInfo>,
+    submission: Option<TransactionInfo>,
+    requests: Mutex<Vec<(&'static str, String)>>,
+}
+
+impl MockAptosRpc {
+    /// Emit events with `data` from the event handle `field_name`, numbered
+    /// from sequence number 0
+    pub fn with_events(mut self, field_name: &str, data: Vec<serde_json::Value>) -> Self {
+        self.events.insert(field_name.to_owned(), data);
+        self
+    }
+
+    /// Store a resource of type `resource_type` holding `data` under every account
+    pub fn with_resource(mut self, resource_type: &str, data: serde_json::Value) -> Self {
+        self.resources.insert(resource_type.to_owned(), data);
+        self
+    }
+
+    /// Estimate `gas_estimate` as the gas unit price
+    pub fn with_gas_estimate(mut self, gas_estimate: u64) -> Self {
+        self.gas_estimate = Some(gas_estimate);
+        self
+    }
+
+    /// Answer simulations with `info`
+    pub fn with_simulation(mut self, info: TransactionInfo) -> Self {
+        self.simulation = Some(info);
+        self
+    }
+
+    /// Commit submitted transactions with the outcome `info`
+    pub fn with_submission(mut self, info: TransactionInfo) -> Self {
+        self.submission = Some(info);
+        self
+    }
+
+    /// Answer calls to the view function `module::function` with `values`
+    pub fn with_view(mut self, function: &str, values: Vec<serde_json::Value>) -> Self {
+        self.views.insert(function.to_owned(), values);
+        self
+    }
+
+    /// Requests made to `method`, with their arguments rendered as a string
+    pub fn requests(&self, method: &str) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(m, _)| *m == method)
+            .map(|(_, args)| args.clone())
+            .collect()
+    }
+
+    fn record(&self, method: &'static str, args: String) {
+        self.requests.lock().unwrap().push((method, args));
+    }
+
+    fn respond<T>(&self, inner: T) -> Result<Response<T>, RestError> {
+        Ok(Response::new(inner, ledger_state()))
+    }
+}
+
+fn ledger_state() -> State {
+    State {
+        chain_id: 4,
+        epoch: 1,
+        version: 1,
+        timestamp_usecs: 1,
+        oldest_ledger_version: 0,
+        oldest_block_height: 0,
+        block_height: 1,
+        cursor: None,
+    }
+}
+
+/// Events the node returns per request when no limit is given
+const EVENTS_DEFAULT_LIMIT: u16 = 25;
+
+fn versioned_event(sequence_number: usize, data: &serde_json::Value) -> VersionedEvent {
+    serde_json::from_value(serde_json::json!({
+        "version": (sequence_number + 1).to_string(),
+        "guid": { "creation_number": "0", "account_address": "0x1" },
+        "sequence_number": sequence_number.to_string(),
+        "type": "0x1::mock::Event",
+        "data": data,
+    }))
+    .unwrap()
+}
+
+/// A committed user transaction with the outcome `info`
+fn user_transaction(info: &TransactionInfo, gas_unit_price: u64) -> UserTransaction {
+    serde_json::from_value(user_transaction_json(info, gas_unit_price)).unwrap()
+}
+
+fn user_transaction_json(info: &TransactionInfo, gas_unit_price: u64) -> serde_json::Value {
+    let mut transaction = serde_json::to_value(info).unwrap();
+    let fields = transaction.as_object_mut().unwrap();
+    fields.extend(
+        serde_json::json!({
+            "sender": "0x1",
+            "sequence_number": "0",
+            "max_gas_amount": info.gas_used.to_string(),
+            "gas_unit_price": gas_unit_price.to_string(),
+            "expiration_timestamp_secs": "1",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::mock::call",
+                "type_arguments": [],
+                "arguments": [],
+            },
+            "signature": null,
+            "events": [],
+            "timestamp": "1",
+        })
+        .as_object()
+        .unwrap()
+        .clone(),
+    );
+    transaction
+}
+
+fn not_mocked<T>(method: &str) -> Result<T, RestError> {
+    Err(RestError::Unknown(anyhow!("{method} is not mocked")))
+}
+
+#[async_trait]
+impl AptosRpc for MockAptosRpc {
+    async fn get_ledger_information(&self) -> Result<Response<State>, RestError> {
+        self.record("get_ledger_information", String::new());
+        self.respond(ledger_state())
+    }
+
+    async fn estimate_gas_price(&self) -> Result<Response<GasEstimation>, RestError> {
+        self.record("estimate_gas_price", String::new());
+        match self.gas_estimate {
+            Some(gas_estimate) => self.respond(
+                serde_json::from_value(serde_json::json!({ "gas_estimate": gas_estimate }))
+                    .unwrap(),
+            ),
+            None => not_mocked("estimate_gas_price"),
+        }
+    }
+
+    async fn get_account(&self, address: AccountAddress) -> Result<Response<Account>, RestError> {
+        self.record("get_account", address.to_hex_literal());
+        self.respond(
+            serde_json::from_value(serde_json::json!({
+                "authentication_key": format!("0x{}", address.to_hex()),
+                "sequence_number": "0",
+            }))
+            .unwrap(),
+        )
+    }
+
+    async fn get_account_events(
+        &self,
+        _address: AccountAddress,
+        _struct_tag: &str,
+        field_name: &str,
+        start: Option<u64>,
+        limit: Option<u16>,
+    ) -> Result<Response<Vec<VersionedEvent>>, RestError> {
+        self.record("get_account_events", format!("{field_name}@{start:?}"));
+        let Some(events) = self.events.get(field_name) else {
+            return not_mocked("get_account_events");
+        };
+        // like the node, cap the page size and return the latest page when
+        // no start is given
+        let limit = limit.unwrap_or(EVENTS_DEFAULT_LIMIT).min(EVENTS_PAGE_LIMIT) as usize;
+        let start = start
+            .map(|start| start as usize)
+            .unwrap_or_else(|| events.len().saturating_sub(limit));
+        self.respond(
+            events
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take(limit)
+                .map(|(sequence_number, data)| versioned_event(sequence_number, data))
+                .collect(),
+        )
+    }
+
+    async fn get_account_module(
+        &self,
+        _address: AccountAddress,
+        _module_name: &str,
+    ) -> Result<Response<MoveModuleBytecode>, RestError> {
+        not_mocked("get_account_module")
+    }
+
+    async fn get_account_modules(
+        &self,
+        _address: AccountAddress,
+    ) -> Result<Response<Vec<MoveModuleBytecode>>, RestError> {
+        not_mocked("get_account_modules")
+    }
+
+    async fn get_account_resource(
+        &self,
+        _address: AccountAddress,
+        resource_type: &str,
+    ) -> Result<Response<Option<Resource>>, RestError> {
+        self.record("get_account_resource", resource_type.to_owned());
+        self.respond(self.resources.get(resource_type).map(|data| {
+            serde_json::from_value(serde_json::json!({
+                "type": resource_type,
+                "data": data,
+            }))
+            .unwrap()
+        }))
+    }
+
+    async fn get_block_by_height(
+        &self,
+        _height: u64,
+        _with_transactions: bool,
+    ) -> Result<Response<Block>, RestError> {
+        not_mocked("get_block_by_height")
+    }
+
+    async fn get_transaction_by_hash(
+        &self,
+        _hash: HashValue,
+    ) -> Result<Response<Transaction>, RestError> {
+        not_mocked("get_transaction_by_hash")
+    }
+
+    async fn simulate(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Vec<UserTransaction>>, RestError> {
+        self.record(
+            "simulate",
+            format!("gas_unit_price={}", txn.gas_unit_price()),
+        );
+        match &self.simulation {
+            Some(info) => self.respond(vec![user_transaction(info, txn.gas_unit_price())]),
+            None => not_mocked("simulate"),
+        }
+    }
+
+    async fn submit_and_wait(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<Transaction>, RestError> {
+        self.record(
+            "submit_and_wait",
+            format!("gas_unit_price={}", txn.gas_unit_price()),
+        );
+        let Some(info) = &self.submission else {
+            return not_mocked("submit_and_wait");
+        };
+        let mut transaction = user_transaction_json(info, txn.gas_unit_price());
+        transaction["type"] = serde_json::json!("user_transaction");
+        self.respond(serde_json::from_value(transaction).unwrap())
+    }
+
+    async fn view(
+        &self,
+        request: &ViewRequest,
+        _version: Option<u64>,
+    ) -> Result<Response<Vec<serde_json::Value>>, RestError> {
+        let function = request.function.to_string();
+        self.record("view", format!("{function}{:?}", request.arguments));
+        match self
+            .views
+            .iter()
+            .find(|(name, _)| function.ends_with(&format!("::{name}")))
+        {
+            Some((_, values)) => self.respond(values.clone()),
+            None => not_mocked("view"),
+        }
+    }
+
+    fn path_prefix_string(&self) -> String {
+        "http://mock.aptos/v1".to_owned()
+    }
+}
@@ -4,12 +4,13 @@
 use async_trait::async_trait;
 
 use hyperlane_core::{
-    ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain, HyperlaneContract,
-    HyperlaneDomain, HyperlaneMessage, HyperlaneProvider, MultisigIsm, RawHyperlaneMessage, H256,
+    ChainCommunicationError, ChainResult, Checkpoint, CheckpointWithMessageId, ContractLocator,
+    HyperlaneChain, HyperlaneContract, HyperlaneDomain, HyperlaneMessage, HyperlaneProvider,
+    MultisigIsm, RawHyperlaneMessage, Signable, H256,
 };
 use solana_sdk::signature::Keypair;
 
-use crate::{AptosHpProvider, ConnectionConf};
+use crate::{AptosHpProvider, AptosRpcMetrics, ConnectionConf};
 use serde::{Deserialize, Serialize};
 
 use crate::utils;
@@ -32,9 +33,41 @@ use aptos_sdk::{
     },
 };
 
+use futures_util::future::try_join_all;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::future::Future;
 use std::str::FromStr;
+
+/// Errors raised when a multisig ISM reports an unsafe validator set
+#[derive(thiserror::Error, Debug)]
+pub enum AptosMultisigIsmError {
+    /// The validator set contains the zero address
+    #[error("Validator set contains the zero address")]
+    ZeroValidator,
+    /// The validator set contains the same validator more than once
+    #[error("Validator {0:?} appears more than once in the validator set")]
+    DuplicateValidator(H256),
+    /// The threshold is zero, so any metadata would verify
+    #[error("Threshold is zero")]
+    ZeroThreshold,
+    /// The threshold can never be reached by the validator set
+    #[error("Threshold {0} exceeds the {1} validators in the set")]
+    ThresholdTooLarge(u8, usize),
+}
+
+impl From<AptosMultisigIsmError> for ChainCommunicationError {
+    fn from(err: AptosMultisigIsmError) -> Self {
+        ChainCommunicationError::from_other(err)
+    }
+}
+
+/// Offset of the signatures in multisig ISM metadata, following the origin
+/// mailbox and merkle root (see `hp_library::ism_metadata`)
+pub(crate) const MULTISIG_SIGNATURES_OFFSET: usize = 64;
+/// Length of a recoverable secp256k1 signature in multisig ISM metadata
+pub(crate) const MULTISIG_SIGNATURE_LENGTH: usize = 65;
+
 /// A reference to a MultisigIsm contract on some Aptos chain
-#[derive(Debug)]
 pub struct AptosMultisigISM {
     payer: Option<Keypair>,
     domain: HyperlaneDomain,
@@ -46,10 +79,32 @@ pub struct AptosMultisigISM {
 impl AptosMultisigISM {
     /// Create a new Aptos MultisigIsm.
     pub fn new(conf: &ConnectionConf, locator: ContractLocator, payer: Option<Keypair>) -> Self {
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
-        let aptos_client = AptosClient::new(conf.url.to_string());
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(locator, payer, aptos_client)
+    }
+
+    /// Create an Aptos MultisigIsm sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        Self::from_client(locator, payer, aptos_client)
+    }
+
+    /// Record metrics for the requests this MultisigIsm makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
 
+    fn from_client(
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        let package_address = utils::h256_to_account_address(&locator.address);
         Self {
             payer,
             domain: locator.domain.clone(),
@@ -59,9 +114,15 @@ impl AptosMultisigISM {
     }
 }
 
+impl std::fmt::Debug for AptosMultisigISM {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self as &dyn HyperlaneContract)
+    }
+}
+
 impl HyperlaneContract for AptosMultisigISM {
     fn address(&self) -> H256 {
-        self.package_address.into_bytes().into()
+        utils::account_address_to_h256(&self.package_address)
     }
 }
 
@@ -70,13 +131,91 @@ impl HyperlaneChain for AptosMultisigISM {
         &self.domain
     }
     fn provider(&self) -> Box<dyn HyperlaneProvider> {
-        Box::new(AptosHpProvider::new(
+        Box::new(AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client.path_prefix_string(),
+            self.aptos_client.clone(),
         ))
     }
 }
 
+impl AptosMultisigISM {
+    /// Returns the validators and threshold for each of `origins`. The view
+    /// API calls one function per request, so each distinct origin is
+    /// requested once and all requests are made concurrently.
+    pub async fn validators_and_threshold_batch(
+        &self,
+        origins: &[u32],
+    ) -> ChainResult<HashMap<u32, (Vec<H256>, u8)>> {
+        validators_and_threshold_with(origins, |origin| {
+            self.fetch_validators_and_threshold(origin)
+        })
+        .await
+    }
+
+    async fn fetch_validators_and_threshold(&self, origin: u32) -> ChainResult<(Vec<H256>, u8)> {
+        fetch_validators_and_threshold(&self.aptos_client, self.package_address, origin).await
+    }
+}
+
+/// Fetch the validators and threshold the multisig ISM at `package_address`
+/// uses for messages from `origin`
+pub(crate) async fn fetch_validators_and_threshold(
+    aptos_client: &AptosClient,
+    package_address: AccountAddress,
+    origin: u32,
+) -> ChainResult<(Vec<H256>, u8)> {
+    let view_response = utils::send_view_request(
+        aptos_client,
+        package_address.to_hex_literal(),
+        "multisig_ism".to_string(),
+        "validators_and_threshold".to_string(),
+        vec![],
+        vec![serde_json::json!(origin)],
+    )
+    .await?;
+    decode_validators_and_threshold(&view_response)
+}
+
+/// Whether `multisig_ism::verify` would accept `metadata` for `message`: the
+/// first `threshold` signatures must recover to members of `validators`, in
+/// the order of the validator set, over the checkpoint of `message`.
+pub(crate) fn multisig_metadata_verifies(
+    message: &HyperlaneMessage,
+    metadata: &[u8],
+    validators: &[H256],
+    threshold: u8,
+) -> bool {
+    let signatures = match metadata.get(MULTISIG_SIGNATURES_OFFSET..) {
+        Some(signatures) if signatures.len() % MULTISIG_SIGNATURE_LENGTH == 0 => signatures,
+        _ => return false,
+    };
+    if signatures.len() / MULTISIG_SIGNATURE_LENGTH < usize::from(threshold) {
+        return false;
+    }
+    let digest = CheckpointWithMessageId {
+        checkpoint: Checkpoint {
+            mailbox_address: H256::from_slice(&metadata[..32]),
+            mailbox_domain: message.origin,
+            root: H256::from_slice(&metadata[32..MULTISIG_SIGNATURES_OFFSET]),
+            index: message.nonce,
+        },
+        message_id: message.id(),
+    }
+    .eth_signed_message_hash();
+
+    // signatures are ordered by validator, so each signer must come after
+    // the previous one in the validator set
+    let mut remaining_validators = validators.iter();
+    signatures
+        .chunks(MULTISIG_SIGNATURE_LENGTH)
+        .take(threshold.into())
+        .all(|signature| {
+            utils::recover_eth_address(digest, signature).map_or(false, |signer| {
+                remaining_validators.any(|validator| *validator == H256::from(signer))
+            })
+        })
+}
+
 #[async_trait]
 impl MultisigIsm for AptosMultisigISM {
     /// Returns the validator and threshold needed to verify message
@@ -84,25 +223,272 @@ impl MultisigIsm for AptosMultisigISM {
         &self,
         message: &HyperlaneMessage,
     ) -> ChainResult<(Vec<H256>, u8)> {
-        let view_response = utils::send_view_request(
-            &self.aptos_client,
-            self.package_address.to_hex_literal(),
-            "multisig_ism".to_string(),
-            "validators_and_threshold".to_string(),
-            vec![],
-            vec![serde_json::json!(message.origin)],
-        )
-        .await?;
-        let validators: Vec<H256> =
-            serde_json::from_str::<Vec<String>>(&view_response[0].to_string())
-                .unwrap()
-                .iter()
-                .map(|v| utils::convert_hex_string_to_h256(v).unwrap())
-                .collect();
-        let threshold = serde_json::from_str::<String>(&view_response[1].to_string())
-            .unwrap()
-            .parse::<u8>()
-            .unwrap();
-        Ok((validators, threshold))
+        self.fetch_validators_and_threshold(message.origin).await
+    }
+}
+
+async fn validators_and_threshold_with<F, Fut>(
+    origins: &[u32],
+    fetch: F,
+) -> ChainResult<HashMap<u32, (Vec<H256>, u8)>>
+where
+    F: Fn(u32) -> Fut,
+    Fut: Future<Output = ChainResult<(Vec<H256>, u8)>>,
+{
+    let unique_origins: BTreeSet<u32> = origins.iter().copied().collect();
+    let results = try_join_all(unique_origins.iter().map(|origin| fetch(*origin))).await?;
+    Ok(unique_origins.into_iter().zip(results).collect())
+}
+
+/// Decode the `(vector<address>, u64)` returned by `multisig_ism::validators_and_threshold`
+fn decode_validators_and_threshold(
+    view_response: &[serde_json::Value],
+) -> ChainResult<(Vec<H256>, u8)> {
+    let validators: Vec<H256> = utils::decode_view_return_at(view_response, 0)?;
+    let threshold: u64 = utils::decode_view_return_at(view_response, 1)?;
+    let threshold = u8::try_from(threshold).map_err(ChainCommunicationError::from_other)?;
+    validate_validators_and_threshold(&validators, threshold)?;
+    Ok((validators, threshold))
+}
+
+/// Reject validator sets that would silently weaken verification
+fn validate_validators_and_threshold(
+    validators: &[H256],
+    threshold: u8,
+) -> Result<(), AptosMultisigIsmError> {
+    let mut seen = HashSet::with_capacity(validators.len());
+    for validator in validators {
+        if validator.is_zero() {
+            return Err(AptosMultisigIsmError::ZeroValidator);
+        }
+        if !seen.insert(validator) {
+            return Err(AptosMultisigIsmError::DuplicateValidator(*validator));
+        }
+    }
+    if threshold == 0 {
+        return Err(AptosMultisigIsmError::ZeroThreshold);
+    }
+    if usize::from(threshold) > validators.len() {
+        return Err(AptosMultisigIsmError::ThresholdTooLarge(
+            threshold,
+            validators.len(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use std::str::FromStr;
+
+    use hyperlane_core::{
+        ContractLocator, HyperlaneDomain, HyperlaneMessage, KnownHyperlaneDomain, H160, H256,
+    };
+    use solana_sdk::signature::Keypair;
+
+    use super::{
+        decode_validators_and_threshold, multisig_metadata_verifies,
+        validate_validators_and_threshold, validators_and_threshold_with, AptosMultisigISM,
+        AptosMultisigIsmError,
+    };
+    use crate::ConnectionConf;
+
+    /// A message and its checkpoint signatures by the validators with private
+    /// keys 0x11..11 and 0x22..22, signed over origin mailbox 0x1234 and
+    /// merkle root 0x42..42
+    fn signed_message() -> (HyperlaneMessage, Vec<H256>, [Vec<u8>; 2]) {
+        let message = HyperlaneMessage {
+            version: 3,
+            nonce: 7,
+            origin: 1,
+            sender: H256::from_low_u64_be(0xa11ce),
+            destination: 14411,
+            recipient: H256::from_low_u64_be(0xb0b),
+            body: b"hello".to_vec(),
+        };
+        let validators = [
+            "19e7e376e7c213b7e7e7e46cc70a5dd086daff2a",
+            "1563915e194d8cfba1943570603f7606a3115508",
+        ]
+        .map(|address| H256::from(H160::from_str(address).unwrap()))
+        .to_vec();
+        let signatures = [
+            "ca11cfd9a4df41accf218dcf8790153eb810946832665b145cd4eb4e5802df863a84b7eed83101343b2fe12f20cfc73a027746784160be1358cf7a81deba7ad51c",
+            "9ffb79dbcaeb53b7b7c29df554a779053068574a8aaa4e57d9331a682d31946a73118e55d31883da3158513fd5c73126a359cdf96705d845411ac0fd344d2af41c",
+        ]
+        .map(|signature| hex::decode(signature).unwrap());
+        (message, validators, signatures)
+    }
+
+    fn metadata(root: H256, signatures: &[&[u8]]) -> Vec<u8> {
+        let mut metadata = H256::from_low_u64_be(0x1234).as_bytes().to_vec();
+        metadata.extend_from_slice(root.as_bytes());
+        for signature in signatures {
+            metadata.extend_from_slice(signature);
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_multisig_metadata_verifies_with_threshold_signatures() {
+        let (message, validators, [first, second]) = signed_message();
+        let root = H256::repeat_byte(0x42);
+
+        assert!(multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&first, &second]),
+            &validators,
+            2
+        ));
+        // signatures beyond the threshold are not checked
+        assert!(multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&second, &first]),
+            &validators[1..],
+            1
+        ));
+    }
+
+    #[test]
+    fn test_multisig_metadata_rejects_invalid_signatures() {
+        let (message, validators, [first, second]) = signed_message();
+        let root = H256::repeat_byte(0x42);
+
+        // too few signatures for the threshold
+        assert!(!multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&first]),
+            &validators,
+            2
+        ));
+        // signatures out of validator set order
+        assert!(!multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&second, &first]),
+            &validators,
+            2
+        ));
+        // signed over a different checkpoint
+        assert!(!multisig_metadata_verifies(
+            &message,
+            &metadata(H256::repeat_byte(0x43), &[&first, &second]),
+            &validators,
+            2
+        ));
+        // signer is not in the validator set
+        assert!(!multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&first]),
+            &validators[1..],
+            1
+        ));
+        // truncated signature
+        assert!(!multisig_metadata_verifies(
+            &message,
+            &metadata(root, &[&first[..64]]),
+            &validators,
+            1
+        ));
+    }
+
+    #[test]
+    fn test_debug_omits_signer() {
+        let conf = ConnectionConf::test_default();
+        let payer = Keypair::new();
+        let secret = payer.secret().to_bytes();
+        let ism = AptosMultisigISM::new(
+            &conf,
+            ContractLocator {
+                domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+                address: H256::repeat_byte(1),
+            },
+            Some(payer),
+        );
+
+        let debug = format!("{:?}", ism);
+        assert!(debug.contains(&format!("{:?}", H256::repeat_byte(1))));
+        assert!(!debug.contains(&format!("{:?}", &secret[..])));
+        assert!(!debug.contains(&hex::encode(secret)));
+    }
+
+    #[test]
+    fn test_decode_validators_and_threshold() {
+        let view_response = [
+            serde_json::json!(["0xa11ce", "0xb0b"]),
+            serde_json::json!("2"),
+        ];
+        assert_eq!(
+            decode_validators_and_threshold(&view_response).unwrap(),
+            (
+                vec![H256::from_low_u64_be(0xa11ce), H256::from_low_u64_be(0xb0b)],
+                2
+            )
+        );
+
+        assert!(decode_validators_and_threshold(&view_response[..1]).is_err());
+        assert!(decode_validators_and_threshold(&[
+            view_response[0].clone(),
+            serde_json::json!("256")
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validators_and_threshold_batch_decodes_each_origin() {
+        let fetches = &AtomicUsize::new(0);
+        let results = validators_and_threshold_with(&[1, 14411, 1], |origin| async move {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let view_response = match origin {
+                1 => [serde_json::json!(["0xa11ce"]), serde_json::json!("1")],
+                _ => [
+                    serde_json::json!(["0xa11ce", "0xb0b", "0xca401"]),
+                    serde_json::json!("2"),
+                ],
+            };
+            decode_validators_and_threshold(&view_response)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&1], (vec![H256::from_low_u64_be(0xa11ce)], 1));
+        assert_eq!(
+            results[&14411],
+            (
+                vec![
+                    H256::from_low_u64_be(0xa11ce),
+                    H256::from_low_u64_be(0xb0b),
+                    H256::from_low_u64_be(0xca401)
+                ],
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_validators_and_threshold() {
+        let alice = H256::from_low_u64_be(0xa11ce);
+        let bob = H256::from_low_u64_be(0xb0b);
+
+        assert!(validate_validators_and_threshold(&[alice, bob], 2).is_ok());
+        assert!(matches!(
+            validate_validators_and_threshold(&[alice, H256::zero()], 1),
+            Err(AptosMultisigIsmError::ZeroValidator)
+        ));
+        assert!(matches!(
+            validate_validators_and_threshold(&[alice, bob, alice], 2),
+            Err(AptosMultisigIsmError::DuplicateValidator(v)) if v == alice
+        ));
+        assert!(matches!(
+            validate_validators_and_threshold(&[alice, bob], 0),
+            Err(AptosMultisigIsmError::ZeroThreshold)
+        ));
+        assert!(matches!(
+            validate_validators_and_threshold(&[alice, bob], 3),
+            Err(AptosMultisigIsmError::ThresholdTooLarge(3, 2))
+        ));
     }
 }
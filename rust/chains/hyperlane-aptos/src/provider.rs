@@ -1,14 +1,151 @@
+use std::time::Duration;
+
 use aptos_sdk::crypto::HashValue;
-use aptos_sdk::rest_client::aptos_api_types::Transaction;
+use aptos_sdk::rest_client::aptos_api_types::{AptosErrorCode, Transaction};
+use aptos_sdk::rest_client::error::RestError;
+use aptos_sdk::types::account_address::AccountAddress;
 
 use async_trait::async_trait;
 
 use hyperlane_core::{
-    BlockInfo, ChainResult, HyperlaneChain, HyperlaneDomain, HyperlaneProvider, TxnInfo,
-    TxnReceiptInfo, H256, U256,
+    BlockInfo, ChainCommunicationError, ChainResult, HyperlaneChain, HyperlaneDomain,
+    HyperlaneProvider, HyperlaneProviderError, TxnInfo, TxnReceiptInfo, H256, U256,
 };
 
-use crate::{convert_hex_string_to_h256, AptosClient};
+use serde::Deserialize;
+
+use crate::{
+    utils::{self, account_address_to_h256},
+    AptosClient, AptosRpcMetrics,
+};
+
+/// Errors raised while validating an Aptos provider endpoint
+#[derive(thiserror::Error, Debug)]
+pub enum AptosProviderError {
+    /// The endpoint serves a different network than expected
+    #[error("Expected chain id {expected} but the endpoint reported chain id {actual}")]
+    ChainIdMismatch {
+        /// chain id the provider was configured for
+        expected: u8,
+        /// chain id reported by the endpoint
+        actual: u8,
+    },
+    /// The Aptos REST API only resolves blocks by height or by transaction
+    /// version, so blocks cannot be looked up by hash
+    #[error("Getting block {0:?} by hash is not supported on Aptos")]
+    BlockByHashUnsupported(H256),
+}
+
+impl From<AptosProviderError> for ChainCommunicationError {
+    fn from(err: AptosProviderError) -> Self {
+        ChainCommunicationError::from_other(err)
+    }
+}
+
+fn validate_chain_id(expected: u8, actual: u8) -> Result<(), AptosProviderError> {
+    if expected != actual {
+        return Err(AptosProviderError::ChainIdMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// Current epoch of an Aptos chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochInfo {
+    /// current epoch number
+    pub epoch: u64,
+    /// time left until the next reconfiguration is due
+    pub time_to_next_epoch: Duration,
+}
+
+/// `0x1::reconfiguration::Configuration` resource
+#[derive(Deserialize)]
+struct MoveReconfiguration {
+    epoch: String,
+    last_reconfiguration_time: String,
+}
+
+/// `0x1::block::BlockResource` resource
+#[derive(Deserialize)]
+struct MoveBlockResource {
+    epoch_interval: String,
+}
+
+/// Decode the epoch from the reconfiguration and block resources, relative to
+/// the ledger timestamp `now_usecs`
+fn decode_epoch_info(
+    reconfiguration: serde_json::Value,
+    block_resource: serde_json::Value,
+    now_usecs: u64,
+) -> ChainResult<EpochInfo> {
+    let reconfiguration: MoveReconfiguration =
+        serde_json::from_value(reconfiguration).map_err(ChainCommunicationError::from_other)?;
+    let block_resource: MoveBlockResource =
+        serde_json::from_value(block_resource).map_err(ChainCommunicationError::from_other)?;
+
+    let epoch = reconfiguration
+        .epoch
+        .parse::<u64>()
+        .map_err(ChainCommunicationError::from_other)?;
+    let last_reconfiguration_time = reconfiguration
+        .last_reconfiguration_time
+        .parse::<u64>()
+        .map_err(ChainCommunicationError::from_other)?;
+    let epoch_interval = block_resource
+        .epoch_interval
+        .parse::<u64>()
+        .map_err(ChainCommunicationError::from_other)?;
+
+    let next_epoch_usecs = last_reconfiguration_time.saturating_add(epoch_interval);
+    Ok(EpochInfo {
+        epoch,
+        time_to_next_epoch: Duration::from_micros(next_epoch_usecs.saturating_sub(now_usecs)),
+    })
+}
+
+/// Map a failed transaction lookup, reporting a missing transaction as
+/// `CouldNotFindObjectByHash`
+fn transaction_lookup_error(hash: &H256, err: RestError) -> ChainCommunicationError {
+    match err {
+        RestError::Api(ref api_error)
+            if api_error.error.error_code == AptosErrorCode::TransactionNotFound =>
+        {
+            HyperlaneProviderError::CouldNotFindObjectByHash(*hash).into()
+        }
+        err => ChainCommunicationError::from_other(err),
+    }
+}
+
+/// Convert a committed transaction into `TxnInfo`
+fn txn_info_from_transaction(hash: &H256, transaction: Transaction) -> ChainResult<TxnInfo> {
+    let mut gas_price = None;
+    let mut gas_limit = U256::zero();
+    let mut sender = H256::zero();
+
+    let tx_info = utils::transaction_info(&transaction)?.clone();
+
+    if let Transaction::UserTransaction(tx) = transaction {
+        gas_price = Some(U256::from(tx.request.gas_unit_price.0));
+        gas_limit = U256::from(tx.request.max_gas_amount.0);
+        sender = account_address_to_h256(tx.request.sender.inner());
+    }
+
+    Ok(TxnInfo {
+        hash: *hash,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        gas_price,
+        gas_limit,
+        nonce: tx_info.version.0,
+        sender,
+        recipient: None,
+        receipt: Some(TxnReceiptInfo {
+            gas_used: U256::from(tx_info.gas_used.0),
+            cumulative_gas_used: U256::zero(),
+            effective_gas_price: None,
+        }),
+    })
+}
 
 /// A wrapper around a Aptos provider to get generic blockchain information.
 #[derive(Debug)]
@@ -20,12 +157,79 @@ pub struct AptosHpProvider {
 impl AptosHpProvider {
     /// Create a new Aptos provider.
     pub fn new(domain: HyperlaneDomain, rest_url: String) -> Self {
-        let aptos_client = AptosClient::new(rest_url);
+        let aptos_client = AptosClient::for_domain(rest_url, &domain);
+        Self::from_client(domain, aptos_client)
+    }
+
+    /// Create a provider sharing `aptos_client`, and with it its metrics
+    pub(crate) fn from_client(domain: HyperlaneDomain, aptos_client: AptosClient) -> Self {
         AptosHpProvider {
             domain,
             aptos_client,
         }
     }
+
+    /// Record metrics for the requests this provider makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
+
+    /// Create a new Aptos provider, checking that the endpoint is reachable
+    /// and serves the network with `expected_chain_id`.
+    pub async fn new_validated(
+        domain: HyperlaneDomain,
+        rest_url: String,
+        expected_chain_id: u8,
+    ) -> ChainResult<Self> {
+        let provider = Self::new(domain, rest_url);
+        let state = provider
+            .aptos_client
+            .instrumented(
+                "get_ledger_information",
+                provider.aptos_client.get_ledger_information(),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        validate_chain_id(expected_chain_id, state.chain_id)?;
+        Ok(provider)
+    }
+
+    /// Returns the current epoch and the time left until the next one, so
+    /// submissions can be deferred around epoch boundaries.
+    pub async fn epoch_info(&self) -> ChainResult<EpochInfo> {
+        let state = self
+            .aptos_client
+            .instrumented(
+                "get_ledger_information",
+                self.aptos_client.get_ledger_information(),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        let reconfiguration = self
+            .get_framework_resource("0x1::reconfiguration::Configuration")
+            .await?;
+        let block_resource = self
+            .get_framework_resource("0x1::block::BlockResource")
+            .await?;
+        decode_epoch_info(reconfiguration, block_resource, state.timestamp_usecs)
+    }
+
+    async fn get_framework_resource(&self, resource_type: &str) -> ChainResult<serde_json::Value> {
+        self.aptos_client
+            .instrumented(
+                "get_account_resource",
+                self.aptos_client
+                    .get_account_resource(AccountAddress::ONE, resource_type),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner()
+            .map(|resource| resource.data)
+            .ok_or_else(|| ChainCommunicationError::from_other_str("Framework resource not found"))
+    }
 }
 
 impl HyperlaneChain for AptosHpProvider {
@@ -34,59 +238,165 @@ impl HyperlaneChain for AptosHpProvider {
     }
 
     fn provider(&self) -> Box<dyn HyperlaneProvider> {
-        Box::new(AptosHpProvider::new(
+        Box::new(AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client.path_prefix_string(),
+            self.aptos_client.clone(),
         ))
     }
 }
 
 #[async_trait]
 impl HyperlaneProvider for AptosHpProvider {
-    async fn get_block_by_hash(&self, _hash: &H256) -> ChainResult<BlockInfo> {
-        // getting block by hash is not supported in Aptos
-        todo!() // FIXME
+    async fn get_block_by_hash(&self, hash: &H256) -> ChainResult<BlockInfo> {
+        Err(AptosProviderError::BlockByHashUnsupported(*hash).into())
     }
 
     async fn get_txn_by_hash(&self, hash: &H256) -> ChainResult<TxnInfo> {
         let transaction: Transaction = self
             .aptos_client
-            .get_transaction_by_hash(HashValue::from_slice(hash.as_bytes()).unwrap())
+            .instrumented(
+                "get_transaction_by_hash",
+                self.aptos_client
+                    .get_transaction_by_hash(HashValue::new(hash.0)),
+            )
             .await
-            .unwrap()
+            .map_err(|e| transaction_lookup_error(hash, e))?
             .into_inner();
+        txn_info_from_transaction(hash, transaction)
+    }
+
+    async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
+        // Aptos account can be both normal account & contract account
+        Ok(true)
+    }
+}
 
-        let mut gas_price = None;
-        let mut gas_limit = U256::zero();
-        let mut sender = H256::zero();
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
 
-        let tx_info = transaction.transaction_info().unwrap().clone();
+    use aptos_sdk::rest_client::{
+        aptos_api_types::{AptosError, AptosErrorCode, Transaction},
+        error::{AptosErrorResponse, RestError},
+    };
+    use hyperlane_core::{
+        HyperlaneDomain, HyperlaneProvider, HyperlaneProviderError, KnownHyperlaneDomain, H256,
+        U256,
+    };
 
-        if let Transaction::UserTransaction(tx) = transaction {
-            gas_price = Some(U256::from(tx.request.gas_unit_price.0));
-            gas_limit = U256::from(tx.request.max_gas_amount.0);
-            sender = convert_hex_string_to_h256(&tx.request.sender.to_string()).unwrap();
-        }
+    use super::{
+        decode_epoch_info, transaction_lookup_error, txn_info_from_transaction, validate_chain_id,
+        AptosHpProvider, AptosProviderError, EpochInfo,
+    };
+
+    #[tokio::test]
+    async fn test_get_block_by_hash_is_unsupported() {
+        let provider = AptosHpProvider::new(
+            HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+            "http://127.0.0.1:8080/v1".to_owned(),
+        );
+        let hash = H256::repeat_byte(0xab);
+
+        let err = provider.get_block_by_hash(&hash).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            AptosProviderError::BlockByHashUnsupported(hash).to_string()
+        );
+    }
 
-        Ok(TxnInfo {
-            hash: *hash,
-            max_fee_per_gas: None,
-            max_priority_fee_per_gas: None,
-            gas_price,
-            gas_limit,
-            nonce: tx_info.version.0,
-            sender,
-            recipient: None,
-            receipt: Some(TxnReceiptInfo {
-                gas_used: U256::from(tx_info.gas_used.0),
-                cumulative_gas_used: U256::zero(),
-                effective_gas_price: None,
-            }),
+    fn api_error(error_code: AptosErrorCode) -> RestError {
+        RestError::Api(AptosErrorResponse {
+            error: AptosError::new_with_error_code("lookup failed", error_code),
+            state: None,
         })
     }
 
-    async fn is_contract(&self, _address: &H256) -> ChainResult<bool> {
-        // Aptos account can be both normal account & contract account
-        Ok(true)
+    #[test]
+    fn test_missing_transaction_is_could_not_find_object_by_hash() {
+        let hash = H256::repeat_byte(0xab);
+        let not_found = HyperlaneProviderError::CouldNotFindObjectByHash(hash).to_string();
+
+        let err = transaction_lookup_error(&hash, api_error(AptosErrorCode::TransactionNotFound));
+        assert_eq!(err.to_string(), not_found);
+
+        let err = transaction_lookup_error(&hash, api_error(AptosErrorCode::InternalError));
+        assert_ne!(err.to_string(), not_found);
+    }
+
+    #[test]
+    fn test_found_transaction_is_converted_to_txn_info() {
+        let hash = H256::repeat_byte(0xab);
+        let transaction: Transaction = serde_json::from_value(serde_json::json!({
+            "type": "user_transaction",
+            "version": "1234567",
+            "hash": format!("{:?}", hash),
+            "state_change_hash": format!("0x{}", "11".repeat(32)),
+            "event_root_hash": format!("0x{}", "22".repeat(32)),
+            "gas_used": "850",
+            "success": true,
+            "vm_status": "Executed successfully",
+            "accumulator_root_hash": format!("0x{}", "33".repeat(32)),
+            "changes": [],
+            "sender": "0xa11ce",
+            "sequence_number": "7",
+            "max_gas_amount": "100000",
+            "gas_unit_price": "150",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [],
+            },
+            "events": [],
+            "timestamp": "1700000000000000",
+        }))
+        .unwrap();
+
+        let txn_info = txn_info_from_transaction(&hash, transaction).unwrap();
+        assert_eq!(txn_info.hash, hash);
+        assert_eq!(txn_info.nonce, 1234567);
+        assert_eq!(txn_info.sender, H256::from_low_u64_be(0xa11ce));
+        assert_eq!(txn_info.gas_price, Some(U256::from(150)));
+        assert_eq!(txn_info.gas_limit, U256::from(100000));
+        assert_eq!(txn_info.receipt.unwrap().gas_used, U256::from(850));
+    }
+
+    #[test]
+    fn test_validate_chain_id_rejects_wrong_network() {
+        assert!(validate_chain_id(4, 4).is_ok());
+        assert!(matches!(
+            validate_chain_id(4, 2),
+            Err(AptosProviderError::ChainIdMismatch {
+                expected: 4,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_epoch_info() {
+        let reconfiguration = serde_json::json!({
+            "epoch": "5021",
+            "last_reconfiguration_time": "1700000000000000"
+        });
+        let block_resource = serde_json::json!({
+            "epoch_interval": "7200000000",
+            "height": "123456"
+        });
+
+        let epoch_info = decode_epoch_info(
+            reconfiguration,
+            block_resource,
+            1700000000000000 + 7000000000,
+        )
+        .unwrap();
+        assert_eq!(
+            epoch_info,
+            EpochInfo {
+                epoch: 5021,
+                time_to_next_epoch: Duration::from_secs(200),
+            }
+        );
     }
 }
@@ -1,21 +1,132 @@
+use std::time::Duration;
+
 use url::Url;
 
 use hyperlane_core::{
-    config::{ConfigErrResultExt, ConfigPath, ConfigResult, FromRawConf},
-    ChainCommunicationError,
+    config::{ConfigErrResultExt, ConfigPath, ConfigResult, FromRawConf, StrOrInt},
+    ChainCommunicationError, H256,
+};
+
+use crate::{
+    resolve_gas_unit_price, DEFAULT_MAX_TRANSACTION_SIZE, GAS_UNIT_LIMIT, MAX_MESSAGE_BODY_BYTES,
 };
 
+/// Default number of `process` submissions allowed in flight for one key
+pub const DEFAULT_MAX_CONCURRENT_SUBMISSIONS: u32 = 1;
+
+/// Default time allowed for one `process`, from the recipient lookups until
+/// the transaction is committed
+pub const DEFAULT_PROCESS_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How the gas unit price of submitted transactions is chosen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GasPriceStrategy {
+    /// Always pay this gas unit price
+    Fixed(u64),
+    /// Pay the gas unit price estimated by the node
+    #[default]
+    Reference,
+    /// Pay the gas unit price estimated by the node plus this tip
+    ReferencePlus(u64),
+}
+
+impl GasPriceStrategy {
+    /// Build a strategy from the optional `fixedGasPrice` and `gasPriceTip`
+    /// settings. A fixed price takes precedence over a tip.
+    pub fn from_settings(fixed_gas_price: Option<u64>, gas_price_tip: Option<u64>) -> Self {
+        match (fixed_gas_price, gas_price_tip) {
+            (Some(price), _) => Self::Fixed(price),
+            (None, Some(tip)) => Self::ReferencePlus(tip),
+            (None, None) => Self::Reference,
+        }
+    }
+
+    /// Whether the price depends on the node's estimate
+    pub fn needs_estimate(&self) -> bool {
+        !matches!(self, Self::Fixed(_))
+    }
+
+    /// Resolve the gas unit price given the node's estimate, never going
+    /// below the minimum gas unit price
+    pub fn resolve(&self, gas_estimate: u64) -> u64 {
+        let price = match *self {
+            Self::Fixed(price) => price,
+            Self::Reference => gas_estimate,
+            Self::ReferencePlus(tip) => gas_estimate.saturating_add(tip),
+        };
+        resolve_gas_unit_price(price)
+    }
+}
+
 /// Aptos connection configuration
 #[derive(Debug, Clone)]
 pub struct ConnectionConf {
     /// Fully qualified string to connect to
     pub url: Url,
+    /// Maximum number of `process` submissions in flight for the relayer key.
+    /// Aptos transactions from one account are ordered by sequence number, so
+    /// concurrent submissions beyond this would race for the same number.
+    pub max_concurrent_submissions: u32,
+    /// Maximum gas units a single transaction may consume, so that one
+    /// message can't drain the relayer key
+    pub max_gas_amount: u64,
+    /// Check `delivered` before submitting `process` and skip messages that
+    /// were already delivered, rather than paying gas for a transaction that aborts
+    pub skip_delivered_messages: bool,
+    /// Decode multisig metadata before submitting `process`, so that malformed
+    /// metadata fails with a descriptive error instead of an on-chain abort
+    pub validate_metadata: bool,
+    /// How the gas unit price of submitted transactions is chosen
+    pub gas_price_strategy: GasPriceStrategy,
+    /// Dispatched messages with a body longer than this many bytes are
+    /// skipped by the indexer instead of being decoded
+    pub max_message_body_size: u32,
+    /// Time allowed for one `process`, from the recipient lookups until the
+    /// transaction is committed, so one message can't block the relayer
+    pub process_timeout: Duration,
+    /// Size limit of a signed transaction in bytes. `process` transactions
+    /// that would exceed it are rejected before submission, since the node
+    /// would refuse them anyway
+    pub max_transaction_size: u32,
+    /// Refuse to `process` messages whose payments to the interchain gas
+    /// paymaster don't cover the quote for the gas they paid for
+    pub enforce_gas_payment: bool,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl ConnectionConf {
+    /// Default settings for a local node, for tests that make no requests
+    pub fn test_default() -> Self {
+        Self {
+            url: "http://127.0.0.1:8080/v1".parse().unwrap(),
+            max_concurrent_submissions: DEFAULT_MAX_CONCURRENT_SUBMISSIONS,
+            max_gas_amount: GAS_UNIT_LIMIT,
+            skip_delivered_messages: false,
+            validate_metadata: false,
+            gas_price_strategy: GasPriceStrategy::default(),
+            max_message_body_size: MAX_MESSAGE_BODY_BYTES,
+            process_timeout: DEFAULT_PROCESS_TIMEOUT,
+            max_transaction_size: DEFAULT_MAX_TRANSACTION_SIZE,
+            enforce_gas_payment: false,
+        }
+    }
 }
 
 /// Raw Aptos connection configuration used for better deserialization errors.
 #[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeprecatedRawConnectionConf {
     url: Option<String>,
+    max_concurrent_submissions: Option<StrOrInt>,
+    max_gas_amount: Option<StrOrInt>,
+    skip_delivered_messages: Option<bool>,
+    validate_metadata: Option<bool>,
+    fixed_gas_price: Option<StrOrInt>,
+    gas_price_tip: Option<StrOrInt>,
+    max_message_body_size: Option<StrOrInt>,
+    process_timeout_secs: Option<StrOrInt>,
+    max_transaction_size: Option<StrOrInt>,
+    enforce_gas_payment: Option<bool>,
 }
 
 /// An error type when parsing a connection configuration.
@@ -24,9 +135,31 @@ pub enum ConnectionConfError {
     /// Missing `url` for connection configuration
     #[error("Missing `url` for connection configuration")]
     MissingConnectionUrl,
+    /// Empty `url` for connection configuration
+    #[error("Empty `url` for connection configuration")]
+    EmptyConnectionUrl,
     /// Invalid `url` for connection configuration
     #[error("Invalid `url` for connection configuration: `{0}` ({1})")]
     InvalidConnectionUrl(String, url::ParseError),
+    /// `max_concurrent_submissions` is zero, so no submission could ever start
+    #[error("`max_concurrent_submissions` must be at least 1")]
+    ZeroMaxConcurrentSubmissions,
+    /// A core module address is zero, so every call to the module would abort
+    #[error("`{0}` must be the address of a deployed module, not zero")]
+    ZeroModuleAddress(&'static str),
+}
+
+/// Check that the core module configured under `field` has a non-zero
+/// address, so a missing deployment fails when parsing the config rather
+/// than on the first call to the module
+pub fn validate_module_address(
+    field: &'static str,
+    address: H256,
+) -> Result<H256, ConnectionConfError> {
+    if address.is_zero() {
+        return Err(ConnectionConfError::ZeroModuleAddress(field));
+    }
+    Ok(address)
 }
 
 impl FromRawConf<DeprecatedRawConnectionConf> for ConnectionConf {
@@ -36,16 +169,71 @@ impl FromRawConf<DeprecatedRawConnectionConf> for ConnectionConf {
         _filter: (),
     ) -> ConfigResult<Self> {
         use ConnectionConfError::*;
-        match raw {
-            DeprecatedRawConnectionConf { url: Some(url) } => Ok(Self {
+        let max_concurrent_submissions = raw
+            .max_concurrent_submissions
+            .map(u32::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("max_concurrent_submissions"))?
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_SUBMISSIONS);
+        if max_concurrent_submissions == 0 {
+            return Err(ZeroMaxConcurrentSubmissions)
+                .into_config_result(|| cwp.join("max_concurrent_submissions"));
+        }
+        let max_gas_amount = raw
+            .max_gas_amount
+            .map(u64::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("max_gas_amount"))?
+            .unwrap_or(GAS_UNIT_LIMIT);
+        let fixed_gas_price = raw
+            .fixed_gas_price
+            .map(u64::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("fixed_gas_price"))?;
+        let gas_price_tip = raw
+            .gas_price_tip
+            .map(u64::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("gas_price_tip"))?;
+        let max_message_body_size = raw
+            .max_message_body_size
+            .map(u32::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("max_message_body_size"))?
+            .unwrap_or(MAX_MESSAGE_BODY_BYTES);
+        let process_timeout = raw
+            .process_timeout_secs
+            .map(u64::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("process_timeout_secs"))?
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_PROCESS_TIMEOUT);
+        let max_transaction_size = raw
+            .max_transaction_size
+            .map(u32::try_from)
+            .transpose()
+            .into_config_result(|| cwp.join("max_transaction_size"))?
+            .unwrap_or(DEFAULT_MAX_TRANSACTION_SIZE);
+        match raw.url {
+            Some(url) if url.trim().is_empty() => {
+                Err(EmptyConnectionUrl).into_config_result(|| cwp.join("url"))
+            }
+            Some(url) => Ok(Self {
                 url: url
                     .parse()
                     .map_err(|e| InvalidConnectionUrl(url, e))
                     .into_config_result(|| cwp.join("url"))?,
+                max_concurrent_submissions,
+                max_gas_amount,
+                skip_delivered_messages: raw.skip_delivered_messages.unwrap_or_default(),
+                validate_metadata: raw.validate_metadata.unwrap_or_default(),
+                gas_price_strategy: GasPriceStrategy::from_settings(fixed_gas_price, gas_price_tip),
+                max_message_body_size,
+                process_timeout,
+                max_transaction_size,
+                enforce_gas_payment: raw.enforce_gas_payment.unwrap_or_default(),
             }),
-            DeprecatedRawConnectionConf { url: None } => {
-                Err(MissingConnectionUrl).into_config_result(|| cwp.join("url"))
-            }
+            None => Err(MissingConnectionUrl).into_config_result(|| cwp.join("url")),
         }
     }
 }
@@ -59,3 +247,124 @@ impl From<AptosNewConnectionError> for ChainCommunicationError {
         ChainCommunicationError::from_other(err)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{
+        config::{ConfigPath, FromRawConf},
+        H256,
+    };
+
+    use super::{
+        validate_module_address, ConnectionConf, DeprecatedRawConnectionConf, GasPriceStrategy,
+        DEFAULT_PROCESS_TIMEOUT,
+    };
+    use crate::{DEFAULT_MAX_TRANSACTION_SIZE, GAS_UNIT_PRICE, MAX_MESSAGE_BODY_BYTES};
+
+    fn parse(raw: serde_json::Value) -> Result<ConnectionConf, String> {
+        let raw: DeprecatedRawConnectionConf = serde_json::from_value(raw).unwrap();
+        ConnectionConf::from_config(raw, &ConfigPath::default()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_connection_conf_applies_defaults() {
+        let conf = parse(serde_json::json!({
+            "url": "http://127.0.0.1:8080/v1",
+            "maxGasAmount": "5000",
+        }))
+        .unwrap();
+        assert_eq!(conf.url.as_str(), "http://127.0.0.1:8080/v1");
+        assert_eq!(conf.max_gas_amount, 5000);
+        assert_eq!(conf.max_concurrent_submissions, 1);
+        assert_eq!(conf.max_message_body_size, MAX_MESSAGE_BODY_BYTES);
+        assert_eq!(conf.gas_price_strategy, GasPriceStrategy::Reference);
+        assert!(!conf.skip_delivered_messages);
+        assert!(!conf.enforce_gas_payment);
+        assert_eq!(conf.max_transaction_size, DEFAULT_MAX_TRANSACTION_SIZE);
+        assert_eq!(conf.process_timeout, DEFAULT_PROCESS_TIMEOUT);
+    }
+
+    #[test]
+    fn test_connection_conf_errors_name_the_offending_field() {
+        let err = parse(serde_json::json!({})).unwrap_err();
+        assert!(err.contains("config_path: `url`"));
+        assert!(err.contains("Missing `url`"));
+
+        let err = parse(serde_json::json!({ "url": " " })).unwrap_err();
+        assert!(err.contains("config_path: `url`"));
+        assert!(err.contains("Empty `url`"));
+
+        let err = parse(serde_json::json!({ "url": "not a url" })).unwrap_err();
+        assert!(err.contains("Invalid `url`"));
+
+        let err = parse(serde_json::json!({
+            "url": "http://127.0.0.1:8080/v1",
+            "maxGasAmount": "lots",
+        }))
+        .unwrap_err();
+        assert!(err.contains("maxGasAmount"));
+    }
+
+    #[test]
+    fn test_connection_conf_rejects_zero_concurrent_submissions() {
+        let err = parse(serde_json::json!({
+            "url": "http://127.0.0.1:8080/v1",
+            "maxConcurrentSubmissions": 0,
+        }))
+        .unwrap_err();
+        assert!(err.contains("config_path: `maxConcurrentSubmissions`"));
+        assert!(err.contains("must be at least 1"));
+
+        let conf = parse(serde_json::json!({
+            "url": "http://127.0.0.1:8080/v1",
+            "maxConcurrentSubmissions": 3,
+        }))
+        .unwrap();
+        assert_eq!(conf.max_concurrent_submissions, 3);
+    }
+
+    #[test]
+    fn test_validate_module_address_rejects_zero() {
+        let mailbox = H256::repeat_byte(0x11);
+        assert_eq!(
+            validate_module_address("mailbox", mailbox).unwrap(),
+            mailbox
+        );
+        assert_eq!(
+            validate_module_address("mailbox", H256::zero())
+                .unwrap_err()
+                .to_string(),
+            "`mailbox` must be the address of a deployed module, not zero"
+        );
+    }
+
+    #[test]
+    fn test_gas_price_strategy_from_settings() {
+        assert_eq!(
+            GasPriceStrategy::from_settings(None, None),
+            GasPriceStrategy::Reference
+        );
+        assert_eq!(
+            GasPriceStrategy::from_settings(None, Some(20)),
+            GasPriceStrategy::ReferencePlus(20)
+        );
+        assert_eq!(
+            GasPriceStrategy::from_settings(Some(500), Some(20)),
+            GasPriceStrategy::Fixed(500)
+        );
+    }
+
+    #[test]
+    fn test_gas_price_strategy_resolve() {
+        assert_eq!(GasPriceStrategy::Fixed(500).resolve(150), 500);
+        assert_eq!(GasPriceStrategy::Reference.resolve(150), 150);
+        assert_eq!(GasPriceStrategy::ReferencePlus(20).resolve(150), 170);
+        assert_eq!(
+            GasPriceStrategy::ReferencePlus(1).resolve(u64::MAX),
+            u64::MAX
+        );
+        // never below the minimum gas unit price
+        assert_eq!(GasPriceStrategy::Fixed(1).resolve(150), GAS_UNIT_PRICE);
+        assert_eq!(GasPriceStrategy::Reference.resolve(1), GAS_UNIT_PRICE);
+    }
+}
@@ -1,9 +1,11 @@
+use std::io;
 use std::str::FromStr;
 
 use aptos_sdk::rest_client::aptos_api_types::VersionedEvent;
 use hyperlane_core::{
     accumulator::{incremental::IncrementalMerkle, TREE_DEPTH},
-    ChainCommunicationError, Decode, HyperlaneMessage, InterchainGasPayment, H256, U256,
+    ChainCommunicationError, ChainResult, Decode, HyperlaneMessage, HyperlaneProtocolError,
+    InterchainGasPayment, H256, U256,
 };
 use serde::{Deserialize, Serialize};
 
@@ -16,23 +18,50 @@ pub struct MoveMerkleTree {
     count: String,
 }
 
-impl From<MoveMerkleTree> for IncrementalMerkle {
-    fn from(val: MoveMerkleTree) -> Self {
-        let mut branches: Vec<H256> = vec![];
-        for branch in val.branch.iter() {
-            branches.push(H256::from_str(branch).unwrap());
-        }
-        if branches.len() < 32 {
-            while branches.len() < 32 {
-                branches.push(H256::zero());
-            }
+impl TryFrom<MoveMerkleTree> for IncrementalMerkle {
+    type Error = ChainCommunicationError;
+
+    fn try_from(val: MoveMerkleTree) -> Result<Self, Self::Error> {
+        let mut branches = val
+            .branch
+            .iter()
+            .map(|branch| H256::from_str(branch).map_err(ChainCommunicationError::from_other))
+            .collect::<Result<Vec<_>, _>>()?;
+        if branches.len() > TREE_DEPTH {
+            return Err(ChainCommunicationError::from_other_str(
+                "Merkle tree has too many branches",
+            ));
         }
-        let count = val.count.parse::<usize>().unwrap();
+        branches.resize(TREE_DEPTH, H256::zero());
+        let count = val
+            .count
+            .parse::<usize>()
+            .map_err(ChainCommunicationError::from_other)?;
+
+        Ok(IncrementalMerkle::plant(
+            branches
+                .try_into()
+                .expect("branches were resized to the tree depth"),
+            count,
+        ))
+    }
+}
 
-        IncrementalMerkle::plant(branches[0..TREE_DEPTH].try_into().unwrap(), count)
+impl utils::FromViewReturn for IncrementalMerkle {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        serde_json::from_value::<MoveMerkleTree>(value.clone())
+            .map_err(ChainCommunicationError::from_other)?
+            .try_into()
     }
 }
 
+/// Largest message body the Aptos mailbox accepts in `dispatch`
+pub const MAX_MESSAGE_BODY_BYTES: u32 = 2 * 1024;
+
+/// Encoded length of a message header: version, nonce, origin, sender,
+/// destination and recipient
+const MESSAGE_HEADER_BYTES: usize = 77;
+
 /// Trait for event types which returns trasaction_hash and block_height
 pub trait TxSpecificData {
     /// return block_height
@@ -71,23 +100,54 @@ impl TryFrom<VersionedEvent> for DispatchEventData {
     }
 }
 
+impl DispatchEventData {
+    /// Whether the message body is longer than `max_body_size` bytes. Checked
+    /// on the hex string, so an oversized message is never decoded.
+    pub fn body_exceeds(&self, max_body_size: u32) -> bool {
+        let encoded_len = self.message.trim_start_matches("0x").len();
+        encoded_len > 2 * (MESSAGE_HEADER_BYTES + max_body_size as usize)
+    }
+
+    /// Decode the dispatched message, rejecting bodies longer than
+    /// `max_body_size` bytes before the hex is decoded
+    pub fn to_message(
+        &self,
+        max_body_size: u32,
+    ) -> Result<HyperlaneMessage, HyperlaneProtocolError> {
+        if self.body_exceeds(max_body_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("message body exceeds {} bytes", max_body_size),
+            )
+            .into());
+        }
+        let hex_bytes = hex::decode(self.message.trim_start_matches("0x"))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        HyperlaneMessage::read_from(&mut &hex_bytes[..])
+    }
+}
+
 impl TryInto<HyperlaneMessage> for DispatchEventData {
-    type Error = hyperlane_core::HyperlaneProtocolError;
+    type Error = HyperlaneProtocolError;
     fn try_into(self) -> Result<HyperlaneMessage, Self::Error> {
-        let hex_bytes = hex::decode(&self.message.trim_start_matches("0x")).unwrap();
-        HyperlaneMessage::read_from(&mut &hex_bytes[..])
+        self.to_message(MAX_MESSAGE_BODY_BYTES)
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// Move Value Data of GasPayment Event
+///
+/// Field names must match `hp_igps::events::GasPaymentEvent` exactly, since the
+/// event data is decoded from the JSON emitted by the Aptos REST API:
+/// `message_id`, `gas_amount`, `required_payment`, `block_height`, `transaction_hash`.
 pub struct GasPaymentEventData {
     /// hyperlane message id
     pub message_id: String,
     /// gas amount
     pub gas_amount: String,
-    /// quoted gas payment
-    pub required_amount: String,
+    /// gas payment; `pay_for_gas` withdraws exactly this quoted amount from
+    /// the payer, so it is also the amount actually paid
+    pub required_payment: String,
     /// block number
     pub block_height: String,
     /// hash of transaction
@@ -105,14 +165,15 @@ impl TryFrom<VersionedEvent> for GasPaymentEventData {
 impl TryInto<InterchainGasPayment> for GasPaymentEventData {
     type Error = ChainCommunicationError;
     fn try_into(self) -> Result<InterchainGasPayment, Self::Error> {
+        // u64/u256 event fields are encoded as decimal strings; `U256::from_str`
+        // would parse them as hex
         Ok(InterchainGasPayment {
-            message_id: utils::convert_hex_string_to_h256(&self.message_id).unwrap(),
-            payment: U256::from_str(&self.required_amount)
-                .map_err(ChainCommunicationError::from_other)
-                .unwrap(),
-            gas_amount: U256::from_str(&self.gas_amount)
-                .map_err(ChainCommunicationError::from_other)
-                .unwrap(),
+            message_id: utils::convert_hex_string_to_h256(&self.message_id)
+                .map_err(|_| ChainCommunicationError::from_other_str("Invalid message id"))?,
+            payment: U256::from_dec_str(&self.required_payment)
+                .map_err(ChainCommunicationError::from_other)?,
+            gas_amount: U256::from_dec_str(&self.gas_amount)
+                .map_err(ChainCommunicationError::from_other)?,
         })
     }
 }
@@ -166,3 +227,146 @@ impl TxSpecificData for MsgProcessEventData {
         self.transaction_hash.clone()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::{
+        accumulator::incremental::IncrementalMerkle, Encode, HyperlaneMessage,
+        InterchainGasPayment, H256, U256,
+    };
+
+    use super::{DispatchEventData, GasPaymentEventData, MAX_MESSAGE_BODY_BYTES};
+    use crate::utils::{convert_hex_string_to_h256, FromViewReturn};
+
+    #[test]
+    fn test_merkle_tree_decodes_view_return() {
+        let value = serde_json::json!({
+            "branch": [format!("{:?}", H256::repeat_byte(1))],
+            "count": "1",
+        });
+
+        let tree = IncrementalMerkle::from_view_return(&value).unwrap();
+        assert_eq!(tree.count(), 1);
+        assert_eq!(tree.branch()[0], H256::repeat_byte(1));
+    }
+
+    #[test]
+    fn test_malformed_merkle_tree_is_an_error() {
+        let bad_count = serde_json::json!({ "branch": [], "count": "many" });
+        assert!(IncrementalMerkle::from_view_return(&bad_count).is_err());
+
+        let bad_branch = serde_json::json!({ "branch": ["0xzz"], "count": "0" });
+        assert!(IncrementalMerkle::from_view_return(&bad_branch).is_err());
+    }
+
+    #[test]
+    fn test_gas_payment_event_data_decodes_move_event() {
+        // Shape of `hp_igps::events::GasPaymentEvent` as returned by the REST API:
+        // u64/u256 values are strings, vector<u8> values are hex strings.
+        let data = serde_json::json!({
+            "message_id": "0x5a0d1c3e7f2b4a9c8d6e0f1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e",
+            "gas_amount": "100000",
+            "required_payment": "250000",
+            "block_height": "42",
+            "transaction_hash": "0x01",
+        });
+
+        let event: GasPaymentEventData = serde_json::from_value(data).unwrap();
+        assert_eq!(event.block_height, "42");
+
+        let payment: InterchainGasPayment = event.try_into().unwrap();
+        assert_eq!(
+            payment.message_id,
+            convert_hex_string_to_h256(
+                "0x5a0d1c3e7f2b4a9c8d6e0f1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e"
+            )
+            .unwrap()
+        );
+        assert_eq!(payment.gas_amount, U256::from(100000));
+        assert_eq!(payment.payment, U256::from(250000));
+    }
+
+    #[test]
+    fn test_gas_payment_event_data_rejects_mismatched_fields() {
+        let data = serde_json::json!({
+            "message_id": "0x01",
+            "gas_amount": "100000",
+            "required_amount": "250000",
+            "block_height": "42",
+            "transaction_hash": "0x01",
+        });
+
+        assert!(serde_json::from_value::<GasPaymentEventData>(data).is_err());
+    }
+
+    #[test]
+    fn test_gas_payment_amounts_are_decimal() {
+        let event = GasPaymentEventData {
+            message_id: "0x01".to_string(),
+            gas_amount: "10".to_string(),
+            required_payment: "1000000000000000000000".to_string(),
+            block_height: "42".to_string(),
+            transaction_hash: "0x01".to_string(),
+        };
+
+        let payment: InterchainGasPayment = event.clone().try_into().unwrap();
+        assert_eq!(payment.message_id, H256::from_low_u64_be(1));
+        assert_eq!(payment.gas_amount, U256::from(10));
+        assert_eq!(
+            payment.payment,
+            U256::from(1_000_000_000_000_000_000_000u128)
+        );
+
+        let event = GasPaymentEventData {
+            gas_amount: "0xa".to_string(),
+            ..event
+        };
+        assert!(TryInto::<InterchainGasPayment>::try_into(event).is_err());
+    }
+
+    fn dispatch_event(message: &HyperlaneMessage) -> DispatchEventData {
+        DispatchEventData {
+            dest_domain: message.destination as u64,
+            message: format!("0x{}", hex::encode(message.to_vec())),
+            message_id: format!("{:?}", message.id()),
+            recipient: format!("{:?}", message.recipient),
+            block_height: "42".to_string(),
+            transaction_hash: "0x01".to_string(),
+            sender: format!("{:?}", message.sender),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_event_decodes_message_within_limit() {
+        let message = HyperlaneMessage {
+            body: vec![7; MAX_MESSAGE_BODY_BYTES as usize],
+            ..Default::default()
+        };
+        let event = dispatch_event(&message);
+        assert!(!event.body_exceeds(MAX_MESSAGE_BODY_BYTES));
+
+        let decoded: HyperlaneMessage = event.try_into().unwrap();
+        assert_eq!(decoded.id(), message.id());
+    }
+
+    #[test]
+    fn test_dispatch_event_rejects_oversized_message() {
+        let message = HyperlaneMessage {
+            body: vec![7; 64],
+            ..Default::default()
+        };
+        let event = dispatch_event(&message);
+        assert!(event.body_exceeds(63));
+        assert!(event.to_message(63).is_err());
+        assert!(event.to_message(64).is_ok());
+
+        // The length check runs before decoding, so a huge body that isn't
+        // even valid hex is rejected without being decoded
+        let event = DispatchEventData {
+            message: "zz".repeat(1 << 20),
+            ..event
+        };
+        assert!(event.body_exceeds(MAX_MESSAGE_BODY_BYTES));
+        assert!(TryInto::<HyperlaneMessage>::try_into(event).is_err());
+    }
+}
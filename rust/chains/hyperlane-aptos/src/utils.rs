@@ -1,14 +1,22 @@
-use crate::{AptosClient, TxSpecificData};
+use crate::{
+    client::BLOCK_NUMBER_TTL, AbortCodeDescriptions, AptosClient, GasPriceStrategy, TxSpecificData,
+};
 use anyhow::{Context, Result};
 use aptos_sdk::{
-    crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    crypto::{
+        ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+        multi_ed25519::MultiEd25519PublicKey,
+    },
     move_types::language_storage::TypeTag,
-    move_types::{ident_str, language_storage::ModuleId},
-    rest_client::aptos_api_types::{
-        EntryFunctionId, MoveType, Transaction as AptosTransaction, TransactionInfo,
-        VersionedEvent, ViewRequest,
+    move_types::{ident_str, identifier::Identifier, language_storage::ModuleId},
+    rest_client::{
+        aptos_api_types::{
+            AptosErrorCode, EntryFunctionId, MoveType, Transaction as AptosTransaction,
+            TransactionInfo, VersionedEvent, ViewRequest,
+        },
+        error::RestError,
     },
-    transaction_builder::TransactionFactory,
+    transaction_builder::{TransactionBuilder, TransactionFactory},
     types::{
         account_address::AccountAddress,
         chain_id::ChainId,
@@ -18,39 +26,168 @@ use aptos_sdk::{
         AccountKey, LocalAccount,
     },
 };
-use hyperlane_core::{ChainCommunicationError, ChainResult, LogMeta, H256, H512, U256};
-use solana_sdk::signature::Keypair;
-use std::{ops::RangeInclusive, str::FromStr};
+use futures_util::future::{join3, try_join_all};
+use hyperlane_core::{
+    ChainCommunicationError, ChainResult, HyperlaneMessage, LogMeta, TxOutcome, H160, H256, H512,
+    U256,
+};
+use solana_sdk::{keccak, secp256k1_recover::secp256k1_recover, signature::Keypair};
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    ops::RangeInclusive,
+    str::FromStr,
+};
+use tracing::warn;
 
-/// limit of gas unit
-const GAS_UNIT_LIMIT: u64 = 100000;
+/// default limit of gas unit
+pub const GAS_UNIT_LIMIT: u64 = 100000;
 /// minimum price of gas unit of aptos chains
 pub const GAS_UNIT_PRICE: u64 = 100;
+/// default maximum size of a signed transaction, aptos' 64KiB transaction size limit
+pub const DEFAULT_MAX_TRANSACTION_SIZE: u32 = 64 * 1024;
+/// upper bound on the bytes a signed `handle_message` transaction takes besides
+/// its message and metadata: sender, sequence number, gas fields, expiration,
+/// chain id, entry function id, argument length prefixes and authenticator
+pub const PROCESS_TRANSACTION_OVERHEAD: usize = 1024;
 
-/// Send Aptos Transaction
+/// Clamp a node's gas price estimate to the minimum gas unit price of aptos chains
+pub fn resolve_gas_unit_price(gas_estimate: u64) -> u64 {
+    gas_estimate.max(GAS_UNIT_PRICE)
+}
+
+/// Fetch the gas unit price to pay under `strategy`, only asking the node for
+/// an estimate when the strategy depends on it
+pub async fn get_gas_unit_price(
+    aptos_client: &AptosClient,
+    strategy: GasPriceStrategy,
+) -> ChainResult<u64> {
+    if !strategy.needs_estimate() {
+        return Ok(strategy.resolve(GAS_UNIT_PRICE));
+    }
+    let gas_estimation = aptos_client
+        .instrumented("estimate_gas_price", aptos_client.estimate_gas_price())
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .into_inner();
+    Ok(strategy.resolve(gas_estimation.gas_estimate))
+}
+
+/// Fetch the latest block height, on the same scale as the `block_height`
+/// recorded in hyperlane events and used for `LogMeta::block_number`.
+/// Heights fetched within the last second are reused.
+pub async fn get_finalized_block_number(aptos_client: &AptosClient) -> ChainResult<u32> {
+    aptos_client
+        .block_number_cache()
+        .get_or_fetch(BLOCK_NUMBER_TTL, || async move {
+            let chain_state = aptos_client
+                .instrumented(
+                    "get_ledger_information",
+                    aptos_client.get_ledger_information(),
+                )
+                .await
+                .map_err(ChainCommunicationError::from_other)?
+                .into_inner();
+            chain_state
+                .block_height
+                .try_into()
+                .map_err(ChainCommunicationError::from_other)
+        })
+        .await
+}
+
+/// Send Aptos Transaction. When `fee_payer` is given it pays the gas of the
+/// transaction instead of `signer`, so the signer needn't hold APT.
 pub async fn send_aptos_transaction(
     aptos_client: &AptosClient,
     signer: &mut LocalAccount,
+    fee_payer: Option<&LocalAccount>,
     payload: TransactionPayload,
+    max_gas_amount: u64,
+    gas_price_strategy: GasPriceStrategy,
 ) -> Result<AptosTransaction> {
     let state = aptos_client
-        .get_ledger_information()
+        .instrumented(
+            "get_ledger_information",
+            aptos_client.get_ledger_information(),
+        )
         .await
         .context("Failed in getting chain id")?
         .into_inner();
+    let gas_unit_price = get_gas_unit_price(aptos_client, gas_price_strategy)
+        .await
+        .context("Failed in getting gas unit price")?;
 
     let transaction_factory = TransactionFactory::new(ChainId::new(state.chain_id))
-        .with_gas_unit_price(100)
-        .with_max_gas_amount(GAS_UNIT_LIMIT);
+        .with_gas_unit_price(gas_unit_price)
+        .with_max_gas_amount(max_gas_amount);
 
-    let signed_tx = signer.sign_with_transaction_builder(transaction_factory.payload(payload));
+    let mut retries = 0;
+    loop {
+        let signed_tx = sign_transaction(
+            signer,
+            fee_payer,
+            transaction_factory.payload(payload.clone()),
+        );
 
-    let response = aptos_client
-        .submit_and_wait(&signed_tx)
-        .await
-        .map_err(|e| anyhow::anyhow!(e.to_string()))?
-        .into_inner();
-    Ok(response)
+        match aptos_client
+            .instrumented("submit_and_wait", aptos_client.submit_and_wait(&signed_tx))
+            .await
+        {
+            Ok(response) => return Ok(response.into_inner()),
+            Err(err)
+                if retries < MAX_SEQUENCE_NUMBER_RETRIES && is_sequence_number_conflict(&err) =>
+            {
+                retries += 1;
+                warn!(
+                    sender = %signer.address(),
+                    retries,
+                    error = %err,
+                    "Lost a sequence number race, resubmitting with the latest sequence number"
+                );
+                let account = aptos_client
+                    .instrumented("get_account", aptos_client.get_account(signer.address()))
+                    .await
+                    .context("Failed in getting the sender's sequence number")?
+                    .into_inner();
+                signer.set_sequence_number(account.sequence_number);
+            }
+            Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+        }
+    }
+}
+
+/// Sign the transaction built by `builder` as `signer`, adding the fee-payer
+/// signature of `fee_payer` when given
+fn sign_transaction(
+    signer: &LocalAccount,
+    fee_payer: Option<&LocalAccount>,
+    builder: TransactionBuilder,
+) -> SignedTransaction {
+    match fee_payer {
+        Some(fee_payer) => {
+            signer.sign_fee_payer_with_transaction_builder(vec![], fee_payer, builder)
+        }
+        None => signer.sign_with_transaction_builder(builder),
+    }
+}
+
+/// Number of times a transaction is re-signed after another submission from
+/// the same account took its sequence number
+const MAX_SEQUENCE_NUMBER_RETRIES: usize = 3;
+
+/// Whether the node rejected a transaction because its sequence number was
+/// already used or is being used by a different transaction in the mempool.
+/// Such transactions succeed once re-signed with the account's latest
+/// sequence number.
+fn is_sequence_number_conflict(err: &RestError) -> bool {
+    match err {
+        RestError::Api(api_error) => matches!(
+            api_error.error.error_code,
+            AptosErrorCode::SequenceNumberTooOld | AptosErrorCode::InvalidTransactionUpdate
+        ),
+        _ => false,
+    }
 }
 
 /// Send Aptos Transaction
@@ -58,16 +195,45 @@ pub async fn simulate_aptos_transaction(
     aptos_client: &AptosClient,
     signer: &mut LocalAccount,
     payload: TransactionPayload,
+    max_gas_amount: u64,
+    gas_price_strategy: GasPriceStrategy,
+) -> Result<TransactionInfo> {
+    let gas_unit_price = get_gas_unit_price(aptos_client, gas_price_strategy)
+        .await
+        .context("Failed in getting gas unit price")?;
+    let info = simulate_aptos_transaction_unchecked(
+        aptos_client,
+        signer,
+        payload,
+        max_gas_amount,
+        gas_unit_price,
+    )
+    .await?;
+    check_simulation_status(info.success, &info.vm_status)?;
+    Ok(info)
+}
+
+/// Simulate an Aptos transaction paying `gas_unit_price`, returning its
+/// outcome even when it did not execute successfully
+pub async fn simulate_aptos_transaction_unchecked(
+    aptos_client: &AptosClient,
+    signer: &mut LocalAccount,
+    payload: TransactionPayload,
+    max_gas_amount: u64,
+    gas_unit_price: u64,
 ) -> Result<TransactionInfo> {
     let state = aptos_client
-        .get_ledger_information()
+        .instrumented(
+            "get_ledger_information",
+            aptos_client.get_ledger_information(),
+        )
         .await
         .context("Failed in getting chain id")?
         .into_inner();
 
     let transaction_factory = TransactionFactory::new(ChainId::new(state.chain_id))
-        .with_gas_unit_price(GAS_UNIT_PRICE)
-        .with_max_gas_amount(GAS_UNIT_LIMIT);
+        .with_gas_unit_price(gas_unit_price)
+        .with_max_gas_amount(max_gas_amount);
 
     let raw_tx = transaction_factory
         .payload(payload)
@@ -81,12 +247,30 @@ pub async fn simulate_aptos_transaction(
         Ed25519Signature::try_from([0u8; 64].as_ref()).unwrap(),
     );
 
-    let response_txns = aptos_client.simulate(&signed_tx).await?.into_inner();
-    let response = response_txns[0].clone();
+    let response_txns = aptos_client
+        .instrumented("simulate", aptos_client.simulate(&signed_tx))
+        .await?
+        .into_inner();
+    let response = response_txns
+        .into_iter()
+        .next()
+        .context("Simulation returned no transaction")?;
 
     Ok(response.info)
 }
 
+/// Fail when a simulated transaction did not execute successfully, since its
+/// gas usage and effects are meaningless in that case
+pub(crate) fn check_simulation_status(success: bool, vm_status: &str) -> Result<()> {
+    if !success {
+        return Err(anyhow::anyhow!(
+            "Transaction simulation failed: {}",
+            AbortCodeDescriptions::default().describe_vm_status(vm_status)
+        ));
+    }
+    Ok(())
+}
+
 /// Make Aptos Transaction Payload
 pub fn make_aptos_payload(
     package_address: AccountAddress,
@@ -103,6 +287,109 @@ pub fn make_aptos_payload(
     ))
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+pub(crate) struct AptosMoveCallError(#[from] pub(crate) anyhow::Error);
+
+/// Build a call to the entry function `package::module::function`, checking
+/// that `module` and `function` are valid Move identifiers
+pub fn entry_function_payload(
+    package_address: AccountAddress,
+    module_name: &str,
+    function_name: &str,
+    ty_args: Vec<TypeTag>,
+    args: Vec<Vec<u8>>,
+) -> ChainResult<TransactionPayload> {
+    let identifier = |name: &str| {
+        Identifier::new(name)
+            .map_err(|e| ChainCommunicationError::from_other(AptosMoveCallError(e)))
+    };
+    Ok(TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(package_address, identifier(module_name)?),
+        identifier(function_name)?,
+        ty_args,
+        args,
+    )))
+}
+
+/// Sign `payload` with `payer` and submit it, for tooling that needs to call
+/// entry functions beyond the ones the Hyperlane contracts wrap
+pub async fn submit_move_call(
+    aptos_client: &AptosClient,
+    payer: &Keypair,
+    payload: TransactionPayload,
+    max_gas_amount: u64,
+    gas_price_strategy: GasPriceStrategy,
+) -> ChainResult<TxOutcome> {
+    let mut signer_account = convert_keypair_to_aptos_account(aptos_client, payer).await;
+    let response = send_aptos_transaction(
+        aptos_client,
+        &mut signer_account,
+        None,
+        payload,
+        max_gas_amount,
+        gas_price_strategy,
+    )
+    .await
+    .map_err(|e| ChainCommunicationError::from_other(AptosMoveCallError(e)))?;
+    transaction_outcome(&response)
+}
+
+/// Errors raised when reading the result of a submitted transaction
+#[derive(thiserror::Error, Debug)]
+pub enum AptosTransactionError {
+    /// The node returned the transaction without execution info, i.e. it is
+    /// still pending
+    #[error("Transaction has no execution info, it is still pending")]
+    MissingTransactionInfo,
+}
+
+impl From<AptosTransactionError> for ChainCommunicationError {
+    fn from(err: AptosTransactionError) -> Self {
+        ChainCommunicationError::from_other(err)
+    }
+}
+
+/// Execution info of a committed transaction
+pub fn transaction_info(transaction: &AptosTransaction) -> ChainResult<&TransactionInfo> {
+    transaction
+        .transaction_info()
+        .map_err(|_| AptosTransactionError::MissingTransactionInfo.into())
+}
+
+/// Summarize a committed transaction as a [`TxOutcome`]
+pub fn transaction_outcome(transaction: &AptosTransaction) -> ChainResult<TxOutcome> {
+    let info = transaction_info(transaction)?;
+    let gas_unit_price = match transaction {
+        AptosTransaction::UserTransaction(tx) => tx.request.gas_unit_price.0,
+        _ => GAS_UNIT_PRICE,
+    };
+    outcome_from_info(info, gas_unit_price)
+}
+
+fn outcome_from_info(info: &TransactionInfo, gas_unit_price: u64) -> ChainResult<TxOutcome> {
+    let tx_hash = convert_hex_string_to_h256(&info.hash.to_string())
+        .map_err(|_| ChainCommunicationError::from_other_str("Invalid transaction hash"))?;
+    Ok(TxOutcome {
+        transaction_id: H512::from(tx_hash),
+        executed: info.success,
+        gas_price: U256::from(gas_unit_price),
+        gas_used: U256::from(info.gas_used.0),
+    })
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+struct AptosTypeTagError(#[from] anyhow::Error);
+
+/// Parse a type argument for a generic view function, e.g.
+/// `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`, so that generic modules
+/// can be called through `send_view_request`.
+pub fn parse_type_tag(type_tag: &str) -> ChainResult<MoveType> {
+    MoveType::from_str(type_tag)
+        .map_err(|e| ChainCommunicationError::from_other(AptosTypeTagError(anyhow::anyhow!(e))))
+}
+
 /// Send View Request
 pub async fn send_view_request(
     aptos_client: &AptosClient,
@@ -112,43 +399,456 @@ pub async fn send_view_request(
     type_arguments: Vec<MoveType>,
     arguments: Vec<serde_json::Value>,
 ) -> ChainResult<Vec<serde_json::Value>> {
+    let request = ViewRequest {
+        function: EntryFunctionId::from_str(&format!(
+            "{package_address}::{module_name}::{function_name}"
+        ))
+        .unwrap(),
+        type_arguments,
+        arguments,
+    };
     let view_response = aptos_client
-        .view(
-            &ViewRequest {
-                function: EntryFunctionId::from_str(&format!(
-                    "{package_address}::{module_name}::{function_name}"
-                ))
-                .unwrap(),
-                type_arguments,
-                arguments,
-            },
-            Option::None,
-        )
+        .instrumented("view", aptos_client.view(&request, Option::None))
         .await
         .map_err(ChainCommunicationError::from_other)?
         .into_inner();
     Ok(view_response)
 }
 
-/// Convert address string to H256
+/// Convert a `vector<u8>` view result into bytes.
+/// Accepts both a `0x`-prefixed hex string and a JSON array of numbers.
+pub fn convert_view_value_to_bytes(value: &serde_json::Value) -> ChainResult<Vec<u8>> {
+    match value {
+        serde_json::Value::String(hex_str) => {
+            let hex_str = hex_str.strip_prefix("0x").ok_or_else(|| {
+                ChainCommunicationError::from_other_str("Byte vector is not 0x-prefixed")
+            })?;
+            hex::decode(hex_str).map_err(ChainCommunicationError::from_other)
+        }
+        serde_json::Value::Array(_) => serde_json::from_value::<Vec<u8>>(value.clone())
+            .map_err(ChainCommunicationError::from_other),
+        _ => Err(ChainCommunicationError::from_other_str(
+            "Unexpected byte vector encoding",
+        )),
+    }
+}
+
+/// A type that can be decoded from a single JSON-encoded view function return value.
+///
+/// The aptos REST API encodes `u64`, `u128` and `u256` as decimal strings,
+/// `address` as a `0x`-prefixed hex string, and smaller integers and `bool` natively.
+pub trait FromViewReturn: Sized {
+    /// Decode `value` into `Self`
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self>;
+}
+
+impl FromViewReturn for bool {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        serde_json::from_value(value.clone()).map_err(ChainCommunicationError::from_other)
+    }
+}
+
+impl FromViewReturn for u32 {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        serde_json::from_value(value.clone()).map_err(ChainCommunicationError::from_other)
+    }
+}
+
+impl FromViewReturn for u64 {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        String::from_view_return(value)?
+            .parse()
+            .map_err(ChainCommunicationError::from_other)
+    }
+}
+
+impl FromViewReturn for u128 {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        U256::from_view_return(value)?
+            .try_into()
+            .map_err(|_| ChainCommunicationError::from_other_str("Value overflows u128"))
+    }
+}
+
+impl FromViewReturn for U256 {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        match value {
+            serde_json::Value::String(dec_str) => {
+                U256::from_dec_str(dec_str).map_err(ChainCommunicationError::from_other)
+            }
+            // smaller integers are encoded natively
+            serde_json::Value::Number(number) => number
+                .as_u64()
+                .map(U256::from)
+                .ok_or_else(|| ChainCommunicationError::from_other_str("Invalid integer")),
+            _ => Err(ChainCommunicationError::from_other_str(
+                "Unexpected integer encoding",
+            )),
+        }
+    }
+}
+
+impl FromViewReturn for H256 {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        convert_hex_string_to_h256(&String::from_view_return(value)?)
+            .map_err(|_| ChainCommunicationError::from_other_str("Invalid address"))
+    }
+}
+
+impl FromViewReturn for String {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        serde_json::from_value(value.clone()).map_err(ChainCommunicationError::from_other)
+    }
+}
+
+impl<T: FromViewReturn> FromViewReturn for Vec<T> {
+    fn from_view_return(value: &serde_json::Value) -> ChainResult<Self> {
+        value
+            .as_array()
+            .ok_or_else(|| ChainCommunicationError::from_other_str("Expected a vector"))?
+            .iter()
+            .map(T::from_view_return)
+            .collect()
+    }
+}
+
+/// Decode the first return value of a view function
+pub fn decode_view_return<T: FromViewReturn>(
+    view_response: &[serde_json::Value],
+) -> ChainResult<T> {
+    decode_view_return_at(view_response, 0)
+}
+
+/// Decode the return value at `index` of a view function. Functions returning
+/// a tuple have each element as a separate return value.
+pub fn decode_view_return_at<T: FromViewReturn>(
+    view_response: &[serde_json::Value],
+    index: usize,
+) -> ChainResult<T> {
+    T::from_view_return(view_return_at(view_response, index)?)
+}
+
+/// The raw return value at `index` of a view function
+pub fn view_return_at(
+    view_response: &[serde_json::Value],
+    index: usize,
+) -> ChainResult<&serde_json::Value> {
+    view_response.get(index).ok_or_else(|| {
+        ChainCommunicationError::from_other_str("View function returned too few values")
+    })
+}
+
+/// Send a view request and decode its first return value
+pub async fn send_view_request_decoded<T: FromViewReturn>(
+    aptos_client: &AptosClient,
+    package_address: String,
+    module_name: String,
+    function_name: String,
+    type_arguments: Vec<MoveType>,
+    arguments: Vec<serde_json::Value>,
+) -> ChainResult<T> {
+    let view_response = send_view_request(
+        aptos_client,
+        package_address,
+        module_name,
+        function_name,
+        type_arguments,
+        arguments,
+    )
+    .await?;
+    decode_view_return(&view_response)
+}
+
+/// Compute the id of `message` as the Move mailbox does in `hp_library::msg_utils::id`.
+///
+/// The id is the keccak256 hash of the message as encoded by
+/// `msg_utils::format_message_into_bytes`: version (1 byte), nonce and origin
+/// (4 bytes big-endian each), sender (32 bytes), destination (4 bytes big-endian),
+/// recipient (32 bytes) and body. That is the same encoding and hash as
+/// `HyperlaneMessage::id`, so ids match those computed for other chains.
+pub fn message_id(message: &HyperlaneMessage) -> H256 {
+    message.id()
+}
+
+/// Recover the Ethereum address that produced the 65 byte recoverable
+/// signature `signature` over `digest`
+pub(crate) fn recover_eth_address(digest: H256, signature: &[u8]) -> Option<H160> {
+    let (signature, recovery_id) = match signature {
+        [signature @ .., v] if signature.len() == 64 => (signature, *v),
+        _ => return None,
+    };
+    // accept both the Ethereum (27/28) and the raw (0/1) recovery id encodings
+    let recovery_id = match recovery_id {
+        27 | 28 => recovery_id - 27,
+        0 | 1 => recovery_id,
+        _ => return None,
+    };
+    let public_key = secp256k1_recover(digest.as_bytes(), recovery_id, signature).ok()?;
+    Some(H160::from_slice(
+        &keccak::hash(&public_key.to_bytes()).to_bytes()[12..],
+    ))
+}
+
+/// Read `field` of the resource `resource_type` stored under `account`, for
+/// state that has no dedicated view function. Nested fields are separated by `.`.
+pub async fn get_resource_field(
+    aptos_client: &AptosClient,
+    account: AccountAddress,
+    resource_type: &str,
+    field: &str,
+) -> ChainResult<serde_json::Value> {
+    let data = get_resource_data(aptos_client, account, resource_type).await?;
+    extract_resource_field(&data, field)
+}
+
+/// Fetch the data of the resource `resource_type` stored at `account`
+pub async fn get_resource_data(
+    aptos_client: &AptosClient,
+    account: AccountAddress,
+    resource_type: &str,
+) -> ChainResult<serde_json::Value> {
+    let resource = aptos_client
+        .instrumented(
+            "get_account_resource",
+            aptos_client.get_account_resource(account, resource_type),
+        )
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .into_inner()
+        .ok_or_else(|| ChainCommunicationError::from_other_str("Resource not found"))?;
+    Ok(resource.data)
+}
+
+/// Extract a `.`-separated field path from resource data
+pub fn extract_resource_field(
+    data: &serde_json::Value,
+    field: &str,
+) -> ChainResult<serde_json::Value> {
+    field
+        .split('.')
+        .try_fold(data, |value, name| {
+            value
+                .get(name)
+                .ok_or_else(|| ChainCommunicationError::from_other_str("Resource field not found"))
+        })
+        .cloned()
+}
+
+/// Hyperlane modules published under a single Aptos account
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AptosDeployment {
+    /// Address publishing the `mailbox` module
+    pub mailbox: Option<AccountAddress>,
+    /// Address publishing the `igps` module
+    pub interchain_gas_paymaster: Option<AccountAddress>,
+    /// Address publishing the `multisig_ism` module
+    pub multisig_ism: Option<AccountAddress>,
+    /// Address publishing the `validator_announce` module
+    pub validator_announce: Option<AccountAddress>,
+}
+
+impl AptosDeployment {
+    /// Classify the modules `module_names` published at `account`
+    pub fn from_modules<'a>(
+        account: AccountAddress,
+        module_names: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let mut deployment = Self::default();
+        for name in module_names {
+            let slot = match name {
+                "mailbox" => &mut deployment.mailbox,
+                "igps" => &mut deployment.interchain_gas_paymaster,
+                "multisig_ism" => &mut deployment.multisig_ism,
+                "validator_announce" => &mut deployment.validator_announce,
+                _ => continue,
+            };
+            *slot = Some(account);
+        }
+        deployment
+    }
+}
+
+/// Discover which Hyperlane contracts are published at `account`
+pub async fn discover_deployment(
+    aptos_client: &AptosClient,
+    account: AccountAddress,
+) -> ChainResult<AptosDeployment> {
+    let modules = aptos_client
+        .instrumented(
+            "get_account_modules",
+            aptos_client.get_account_modules(account),
+        )
+        .await
+        .map_err(ChainCommunicationError::from_other)?
+        .into_inner();
+    let names: Vec<String> = modules
+        .into_iter()
+        .filter_map(|module| module.abi.map(|abi| abi.name.to_string()))
+        .collect();
+    Ok(AptosDeployment::from_modules(
+        account,
+        names.iter().map(String::as_str),
+    ))
+}
+
+/// Whether a configured contract address publishes the module expected of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractHealth {
+    /// The account publishes the expected module
+    Healthy,
+    /// The account exists but does not publish the expected module
+    ModuleMissing,
+    /// The account's modules could not be fetched
+    Unreachable(String),
+}
+
+impl ContractHealth {
+    fn from_lookup(
+        account: AccountAddress,
+        lookup: ChainResult<AptosDeployment>,
+        published: impl Fn(&AptosDeployment) -> Option<AccountAddress>,
+    ) -> Self {
+        match lookup {
+            Ok(deployment) if published(&deployment) == Some(account) => Self::Healthy,
+            Ok(_) => Self::ModuleMissing,
+            Err(err) => Self::Unreachable(err.to_string()),
+        }
+    }
+}
+
+/// Reachability of the mailbox, IGP and ISM configured for a chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityReport {
+    /// Status of the mailbox package
+    pub mailbox: ContractHealth,
+    /// Status of the interchain gas paymaster package
+    pub interchain_gas_paymaster: ContractHealth,
+    /// Status of the multisig ISM package
+    pub multisig_ism: ContractHealth,
+}
+
+impl ConnectivityReport {
+    fn from_lookups(
+        (mailbox, mailbox_lookup): (AccountAddress, ChainResult<AptosDeployment>),
+        (igp, igp_lookup): (AccountAddress, ChainResult<AptosDeployment>),
+        (ism, ism_lookup): (AccountAddress, ChainResult<AptosDeployment>),
+    ) -> Self {
+        Self {
+            mailbox: ContractHealth::from_lookup(mailbox, mailbox_lookup, |d| d.mailbox),
+            interchain_gas_paymaster: ContractHealth::from_lookup(igp, igp_lookup, |d| {
+                d.interchain_gas_paymaster
+            }),
+            multisig_ism: ContractHealth::from_lookup(ism, ism_lookup, |d| d.multisig_ism),
+        }
+    }
+
+    /// Whether every contract publishes its expected module
+    pub fn is_healthy(&self) -> bool {
+        [
+            &self.mailbox,
+            &self.interchain_gas_paymaster,
+            &self.multisig_ism,
+        ]
+        .iter()
+        .all(|health| **health == ContractHealth::Healthy)
+    }
+}
+
+/// Check that the configured mailbox, IGP and ISM addresses each publish the
+/// matching Hyperlane module, so a misconfigured address is caught at startup
+/// rather than on first use.
+pub async fn check_connectivity(
+    aptos_client: &AptosClient,
+    mailbox: AccountAddress,
+    interchain_gas_paymaster: AccountAddress,
+    multisig_ism: AccountAddress,
+) -> ConnectivityReport {
+    let (mailbox_lookup, igp_lookup, ism_lookup) = join3(
+        discover_deployment(aptos_client, mailbox),
+        discover_deployment(aptos_client, interchain_gas_paymaster),
+        discover_deployment(aptos_client, multisig_ism),
+    )
+    .await;
+    ConnectivityReport::from_lookups(
+        (mailbox, mailbox_lookup),
+        (interchain_gas_paymaster, igp_lookup),
+        (multisig_ism, ism_lookup),
+    )
+}
+
+/// Convert an Aptos account address to the H256 Hyperlane uses for contract
+/// addresses. Both are 32 big-endian bytes, so the bytes are kept in order and
+/// the address matches the one other chains see in messages.
+pub fn account_address_to_h256(address: &AccountAddress) -> H256 {
+    H256(address.into_bytes())
+}
+
+/// Convert an H256 contract address back to an Aptos account address. The
+/// inverse of `account_address_to_h256`.
+pub fn h256_to_account_address(address: &H256) -> AccountAddress {
+    AccountAddress::new(address.0)
+}
+
+/// Convert address string to H256. Framework addresses such as `0x1` are
+/// displayed in short form, so the hex is left-padded to 32 bytes; a qualified
+/// name like `0x1::aptos_coin::AptosCoin` converts to its account address.
 pub fn convert_hex_string_to_h256(addr: &str) -> Result<H256, String> {
-    let formated_addr = format!("{:0>64}", addr.to_string().trim_start_matches("0x"));
+    let addr = addr.split("::").next().unwrap_or_default();
+    let hex = addr
+        .strip_prefix("0x")
+        .or_else(|| addr.strip_prefix("0X"))
+        .unwrap_or(addr);
+    if hex.is_empty() || hex.len() > 64 {
+        return Err(format!("invalid address length: {}", addr));
+    }
+    let formated_addr = format!("{:0>64}", hex);
     H256::from_str(&formated_addr).map_err(|e| e.to_string())
 }
 
+/// Public key of an Aptos account, by the signature scheme it uses
+#[derive(Debug, Clone)]
+pub enum AccountPublicKey {
+    /// A single ed25519 key
+    Ed25519(Ed25519PublicKey),
+    /// A k-of-n multi-ed25519 key
+    MultiEd25519(MultiEd25519PublicKey),
+}
+
+impl From<Ed25519PublicKey> for AccountPublicKey {
+    fn from(public_key: Ed25519PublicKey) -> Self {
+        Self::Ed25519(public_key)
+    }
+}
+
+impl From<MultiEd25519PublicKey> for AccountPublicKey {
+    fn from(public_key: MultiEd25519PublicKey) -> Self {
+        Self::MultiEd25519(public_key)
+    }
+}
+
+/// Derive the address of the account owned by `public_key`: the sha3-256 hash
+/// of the key followed by its scheme byte (0x00 for ed25519, 0x01 for
+/// multi-ed25519)
+pub fn derive_account_address(public_key: &AccountPublicKey) -> AccountAddress {
+    match public_key {
+        AccountPublicKey::Ed25519(key) => AuthenticationKey::ed25519(key),
+        AccountPublicKey::MultiEd25519(key) => AuthenticationKey::multi_ed25519(key),
+    }
+    .derived_address()
+}
+
 /// Convert payer(Keypair) into Aptos LocalAccount
 pub async fn convert_keypair_to_aptos_account(
     aptos_client: &AptosClient,
     payer: &Keypair,
 ) -> LocalAccount {
     let signer_priv_key = Ed25519PrivateKey::try_from(payer.secret().to_bytes().as_ref()).unwrap();
-    let signer_address =
-        AuthenticationKey::ed25519(&Ed25519PublicKey::from(&signer_priv_key)).derived_address();
+    let signer_address = derive_account_address(&Ed25519PublicKey::from(&signer_priv_key).into());
     let signer_account = LocalAccount::new(
         signer_address,
         AccountKey::from_private_key(signer_priv_key),
         aptos_client
-            .get_account(signer_address)
+            .instrumented("get_account", aptos_client.get_account(signer_address))
             .await
             .map_err(ChainCommunicationError::from_other)
             .unwrap()
@@ -158,6 +858,50 @@ pub async fn convert_keypair_to_aptos_account(
     signer_account
 }
 
+/// Most events the node returns for a single `get_account_events` request
+pub const EVENTS_PAGE_LIMIT: u16 = 100;
+
+/// Fetch every event emitted to the event handle `field_name` of the resource
+/// `struct_tag`, paging by sequence number up to the handle's counter
+pub async fn get_all_account_events(
+    aptos_client: &AptosClient,
+    account_address: AccountAddress,
+    struct_tag: &str,
+    field_name: &str,
+) -> ChainResult<Vec<VersionedEvent>> {
+    let counter = get_resource_field(
+        aptos_client,
+        account_address,
+        struct_tag,
+        &format!("{field_name}.counter"),
+    )
+    .await?;
+    let count = u64::from_view_return(&counter)?;
+
+    let mut events: Vec<VersionedEvent> = Vec::new();
+    while (events.len() as u64) < count {
+        let page = aptos_client
+            .instrumented(
+                "get_account_events",
+                aptos_client.get_account_events(
+                    account_address,
+                    struct_tag,
+                    field_name,
+                    Some(events.len() as u64),
+                    Some(EVENTS_PAGE_LIMIT),
+                ),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        if page.is_empty() {
+            break;
+        }
+        events.extend(page);
+    }
+    Ok(events)
+}
+
 /// Filter events based on range
 pub async fn get_filtered_events<T, S>(
     aptos_client: &AptosClient,
@@ -170,24 +914,47 @@ where
     S: TryFrom<VersionedEvent> + TxSpecificData + TryInto<T> + Clone,
     ChainCommunicationError:
         From<<S as TryFrom<VersionedEvent>>::Error> + From<<S as TryInto<T>>::Error>,
+{
+    get_filtered_event_data::<S>(aptos_client, account_address, struct_tag, field_name, range)
+        .await?
+        .into_iter()
+        .map(|(evt_data, meta)| Ok((evt_data.try_into()?, meta)))
+        .collect()
+}
+
+/// Filter events based on range, returning the raw event data so callers can
+/// inspect it before converting
+pub async fn get_filtered_event_data<S>(
+    aptos_client: &AptosClient,
+    account_address: AccountAddress,
+    struct_tag: &str,
+    field_name: &str,
+    range: RangeInclusive<u32>,
+) -> ChainResult<Vec<(S, LogMeta)>>
+where
+    S: TryFrom<VersionedEvent> + TxSpecificData,
+    ChainCommunicationError: From<<S as TryFrom<VersionedEvent>>::Error>,
 {
     // fetch events from global storage
-    let events: Vec<VersionedEvent> = aptos_client
-        .get_account_events(account_address, struct_tag, field_name, None, Some(10000))
-        .await
-        .map_err(ChainCommunicationError::from_other)?
-        .into_inner();
+    let events =
+        get_all_account_events(aptos_client, account_address, struct_tag, field_name).await?;
 
     // get start block and end block
     let blk_start_no: u32 = *range.start();
     let blk_end_no = *range.end();
     let start_block = aptos_client
-        .get_block_by_height(blk_start_no as u64, false)
+        .instrumented(
+            "get_block_by_height",
+            aptos_client.get_block_by_height(blk_start_no as u64, false),
+        )
         .await
         .map_err(ChainCommunicationError::from_other)?
         .into_inner();
     let end_block = aptos_client
-        .get_block_by_height(blk_end_no as u64, false)
+        .instrumented(
+            "get_block_by_height",
+            aptos_client.get_block_by_height(blk_end_no as u64, false),
+        )
         .await
         .map_err(ChainCommunicationError::from_other)?
         .into_inner();
@@ -200,31 +967,679 @@ where
         .filter(|e| e.version.0 > start_tx_version.0 && e.version.0 <= end_tx_version.0)
         .collect();
 
+    // decode events and resolve the blocks they were emitted in
+    let events = filtered_events
+        .into_iter()
+        .map(|filtered_event| {
+            let evt_data: S = filtered_event.clone().try_into()?;
+            Ok((filtered_event, evt_data))
+        })
+        .collect::<ChainResult<Vec<_>>>()?;
+    let block_heights = events
+        .iter()
+        .map(|(_, evt_data)| event_block_height(evt_data))
+        .collect::<ChainResult<Vec<_>>>()?;
+    let block_hashes = resolve_block_hashes(aptos_client, &block_heights).await?;
+
     // prepare result
-    let mut messages: Vec<(T, LogMeta)> =
-        Vec::with_capacity((range.end() - range.start()) as usize);
-    for filtered_event in filtered_events {
-        let evt_data: S = filtered_event.clone().try_into()?;
-        let block_height = evt_data.block_height().parse().unwrap();
-        let block = aptos_client
-            .get_block_by_height(block_height as u64, false)
-            .await
-            .map_err(ChainCommunicationError::from_other)?
-            .into_inner();
+    let mut messages: Vec<(S, LogMeta)> = Vec::with_capacity(events.len());
+    for ((filtered_event, evt_data), block_height) in events.into_iter().zip(block_heights) {
+        let transaction_id = event_transaction_id(&evt_data)?;
         messages.push((
-            evt_data.clone().try_into()?,
+            evt_data,
             LogMeta {
-                address: account_address.into_bytes().into(),
+                address: account_address_to_h256(&account_address),
                 block_number: block_height,
-                block_hash: convert_hex_string_to_h256(&block.block_hash.to_string()).unwrap(),
-                transaction_id: H512::from(
-                    convert_hex_string_to_h256(&evt_data.transaction_hash()).unwrap(),
-                ),
+                block_hash: block_hashes[&block_height],
+                transaction_id,
                 transaction_index: *filtered_event.version.inner(),
                 log_index: U256::from(*filtered_event.sequence_number.inner()),
             },
         ));
     }
 
+    sort_logs(&mut messages);
     Ok(messages)
 }
+
+/// Height of the block an event was emitted in
+fn event_block_height(evt_data: &impl TxSpecificData) -> ChainResult<u64> {
+    evt_data
+        .block_height()
+        .parse()
+        .map_err(|_| ChainCommunicationError::from_other_str("Invalid event block height"))
+}
+
+/// Hash of the transaction an event was emitted in
+pub(crate) fn event_transaction_id(evt_data: &impl TxSpecificData) -> ChainResult<H512> {
+    convert_hex_string_to_h256(&evt_data.transaction_hash())
+        .map(H512::from)
+        .map_err(|_| ChainCommunicationError::from_other_str("Invalid event transaction hash"))
+}
+
+/// Fetch the hashes of the blocks at `heights`. Each distinct block is
+/// requested once and all requests are made concurrently, so enriching a page
+/// of events costs a single round of requests.
+pub async fn resolve_block_hashes(
+    aptos_client: &AptosClient,
+    heights: &[u64],
+) -> ChainResult<HashMap<u64, H256>> {
+    resolve_blocks_with(heights, |height| async move {
+        let block = aptos_client
+            .instrumented(
+                "get_block_by_height",
+                aptos_client.get_block_by_height(height, false),
+            )
+            .await
+            .map_err(ChainCommunicationError::from_other)?
+            .into_inner();
+        convert_hex_string_to_h256(&block.block_hash.to_string())
+            .map_err(|_| ChainCommunicationError::from_other_str("Invalid block hash"))
+    })
+    .await
+}
+
+async fn resolve_blocks_with<F, Fut>(heights: &[u64], fetch: F) -> ChainResult<HashMap<u64, H256>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = ChainResult<H256>>,
+{
+    let unique_heights: BTreeSet<u64> = heights.iter().copied().collect();
+    let hashes = try_join_all(unique_heights.iter().map(|height| fetch(*height))).await?;
+    Ok(unique_heights.into_iter().zip(hashes).collect())
+}
+
+/// Orders logs by block number and then by log index, so callers see
+/// events in the order they were emitted on chain regardless of the order
+/// the node returned them in.
+fn sort_logs<T>(logs: &mut [(T, LogMeta)]) {
+    logs.sort_by(|(_, a), (_, b)| {
+        a.block_number
+            .cmp(&b.block_number)
+            .then(a.log_index.cmp(&b.log_index))
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use aptos_sdk::{
+        crypto::{
+            ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+            multi_ed25519::MultiEd25519PublicKey,
+        },
+        rest_client::{
+            aptos_api_types::{AptosError, AptosErrorCode, MoveType, Transaction, TransactionInfo},
+            error::{AptosErrorResponse, RestError},
+        },
+        transaction_builder::TransactionFactory,
+        types::{
+            account_address::AccountAddress,
+            chain_id::ChainId,
+            transaction::{authenticator::TransactionAuthenticator, TransactionPayload},
+            AccountKey, LocalAccount,
+        },
+    };
+    use hyperlane_core::{ChainCommunicationError, HyperlaneMessage, LogMeta, H256, H512, U256};
+
+    use super::{
+        account_address_to_h256, check_simulation_status, convert_hex_string_to_h256,
+        convert_view_value_to_bytes, decode_view_return, derive_account_address,
+        entry_function_payload, event_block_height, event_transaction_id, extract_resource_field,
+        get_all_account_events, h256_to_account_address, is_sequence_number_conflict, message_id,
+        outcome_from_info, parse_type_tag, resolve_blocks_with, resolve_gas_unit_price,
+        sign_transaction, sort_logs, transaction_info, AccountPublicKey, AptosDeployment,
+        AptosTransactionError, ConnectivityReport, ContractHealth, GAS_UNIT_PRICE,
+    };
+    use crate::mock::MockAptosRpc;
+    use crate::{AptosClient, TxSpecificData};
+
+    #[tokio::test]
+    async fn test_get_all_account_events_pages_up_to_the_counter() {
+        let events = (0..250).map(|i| serde_json::json!({ "i": i })).collect();
+        let rpc = Arc::new(
+            MockAptosRpc::default()
+                .with_resource(
+                    "0x1::mailbox::MailBoxState",
+                    serde_json::json!({ "dispatch_events": { "counter": "250" } }),
+                )
+                .with_events("dispatch_events", events),
+        );
+        let client = AptosClient::from_rpc(rpc.clone());
+
+        let events = get_all_account_events(
+            &client,
+            AccountAddress::ONE,
+            "0x1::mailbox::MailBoxState",
+            "dispatch_events",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(events.len(), 250);
+        assert!(events
+            .iter()
+            .enumerate()
+            .all(|(i, event)| event.sequence_number.0 == i as u64));
+        assert_eq!(rpc.requests("get_account_events").len(), 3);
+    }
+
+    #[test]
+    fn test_resolve_gas_unit_price_uses_node_estimate() {
+        assert_eq!(resolve_gas_unit_price(150), 150);
+        assert_eq!(resolve_gas_unit_price(GAS_UNIT_PRICE - 1), GAS_UNIT_PRICE);
+    }
+
+    #[test]
+    fn test_convert_view_value_to_bytes() {
+        assert_eq!(
+            convert_view_value_to_bytes(&serde_json::json!("0x6d61696c626f78")).unwrap(),
+            b"mailbox".to_vec()
+        );
+        assert_eq!(
+            convert_view_value_to_bytes(&serde_json::json!([109, 97, 105, 108])).unwrap(),
+            b"mail".to_vec()
+        );
+        assert!(convert_view_value_to_bytes(&serde_json::json!("mailbox")).is_err());
+        assert!(convert_view_value_to_bytes(&serde_json::json!([256])).is_err());
+        assert!(convert_view_value_to_bytes(&serde_json::json!(true)).is_err());
+    }
+
+    #[test]
+    fn test_check_simulation_status() {
+        assert!(check_simulation_status(true, "Executed successfully").is_ok());
+
+        let err = check_simulation_status(
+            false,
+            "Move abort in 0x1::mailbox: ERROR_ALREADY_DELIVERED(0x4): ",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("ERROR_ALREADY_DELIVERED"));
+        assert!(err.to_string().contains("message was already delivered"));
+    }
+
+    #[test]
+    fn test_decode_view_return() {
+        assert!(decode_view_return::<bool>(&[serde_json::json!(true)]).unwrap());
+        assert_eq!(
+            decode_view_return::<u64>(&[serde_json::json!("18446744073709551615")]).unwrap(),
+            u64::MAX
+        );
+        assert_eq!(
+            decode_view_return::<H256>(&[serde_json::json!("0x1")]).unwrap(),
+            H256::from_low_u64_be(1)
+        );
+        assert!(decode_view_return::<u64>(&[serde_json::json!(true)]).is_err());
+        assert!(decode_view_return::<bool>(&[]).is_err());
+    }
+
+    /// RFC 8032 test 1 and test 2 public keys
+    fn rfc8032_public_keys() -> [Ed25519PublicKey; 2] {
+        [
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        ]
+        .map(|key| Ed25519PublicKey::try_from(hex::decode(key).unwrap().as_slice()).unwrap())
+    }
+
+    #[test]
+    fn test_derive_account_address() {
+        let [public_key, _] = rfc8032_public_keys();
+        assert_eq!(
+            derive_account_address(&public_key.into()),
+            AccountAddress::from_hex_literal(
+                "0x63c5215e87770d17b9f4cd47c777e322f4eb152cfd2054c1080fd9d57c48913b"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_derive_account_address_multi_ed25519() {
+        let public_key = MultiEd25519PublicKey::new(rfc8032_public_keys().to_vec(), 1).unwrap();
+        // hashed with the multi-ed25519 scheme byte, so the 1-of-2 key owns
+        // a different account than either of its keys
+        assert_eq!(
+            derive_account_address(&AccountPublicKey::MultiEd25519(public_key)),
+            AccountAddress::from_hex_literal(
+                "0xa789a8c9351a08dfb668780e60d13b5b2d4023ba6d63f8e6626c882519388701"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_extract_resource_field() {
+        // data of a `hp_igps::igps::IgpState` resource
+        let state = serde_json::json!({
+            "owner_address": "0x1",
+            "beneficiary": "0xb0b",
+            "gas_payment_events": {
+                "counter": "3",
+                "guid": { "id": { "addr": "0x1", "creation_num": "4" } }
+            },
+        });
+        assert_eq!(
+            extract_resource_field(&state, "beneficiary").unwrap(),
+            serde_json::json!("0xb0b")
+        );
+        assert_eq!(
+            extract_resource_field(&state, "gas_payment_events.counter").unwrap(),
+            serde_json::json!("3")
+        );
+        assert!(extract_resource_field(&state, "paused").is_err());
+    }
+
+    #[test]
+    fn test_parse_type_tag() {
+        let type_tag = parse_type_tag("0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>").unwrap();
+        match &type_tag {
+            MoveType::Struct(tag) => {
+                assert_eq!(tag.module.to_string(), "coin");
+                assert_eq!(tag.name.to_string(), "CoinStore");
+                assert_eq!(tag.generic_type_params.len(), 1);
+            }
+            _ => panic!("Expected a struct type, got {type_tag}"),
+        }
+
+        assert!(matches!(parse_type_tag("u64").unwrap(), MoveType::U64));
+        assert!(parse_type_tag("0x1::coin::").is_err());
+    }
+
+    #[test]
+    fn test_message_id_matches_known_vector() {
+        // vectors/message.json
+        let message = HyperlaneMessage {
+            version: 0,
+            nonce: 0,
+            origin: 1000,
+            sender: "0x0000000000000000000000001111111111111111111111111111111111111111"
+                .parse()
+                .unwrap(),
+            destination: 2000,
+            recipient: "0x0000000000000000000000002222222222222222222222222222222222222222"
+                .parse()
+                .unwrap(),
+            body: vec![0x12, 0x34],
+        };
+
+        assert_eq!(
+            message_id(&message),
+            "0x545b9ae16e93875efda786a09f3b78221d7f568f46a445fe4cd4a1e38096c576"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_view_return_large_integers() {
+        let u128_max = serde_json::json!(u128::MAX.to_string());
+        assert_eq!(
+            decode_view_return::<u128>(&[u128_max.clone()]).unwrap(),
+            u128::MAX
+        );
+        assert_eq!(
+            decode_view_return::<U256>(&[u128_max]).unwrap(),
+            U256::from(u128::MAX)
+        );
+
+        let above_u128_max = serde_json::json!((U256::from(u128::MAX) + 1).to_string());
+        assert_eq!(
+            decode_view_return::<U256>(&[above_u128_max.clone()]).unwrap(),
+            U256::from(u128::MAX) + 1
+        );
+        assert!(decode_view_return::<u128>(&[above_u128_max]).is_err());
+
+        assert_eq!(
+            decode_view_return::<U256>(&[serde_json::json!(42)]).unwrap(),
+            U256::from(42)
+        );
+        assert!(decode_view_return::<U256>(&[serde_json::json!("0x2a")]).is_err());
+    }
+
+    #[test]
+    fn test_sort_logs_orders_by_block_then_log_index() {
+        let meta = |block_number: u64, log_index: u64| LogMeta {
+            address: H256::zero(),
+            block_number,
+            block_hash: H256::zero(),
+            transaction_id: H512::zero(),
+            transaction_index: 0,
+            log_index: U256::from(log_index),
+        };
+        let mut logs = vec![
+            ("c", meta(12, 0)),
+            ("b", meta(10, 7)),
+            ("d", meta(12, 3)),
+            ("a", meta(10, 2)),
+        ];
+        sort_logs(&mut logs);
+        let order: Vec<_> = logs.iter().map(|(name, _)| *name).collect();
+        assert_eq!(order, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_deployment_from_modules() {
+        let account = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+        let deployment = AptosDeployment::from_modules(
+            account,
+            [
+                "events",
+                "mailbox",
+                "igps",
+                "gas_oracle",
+                "validator_announce",
+            ],
+        );
+        assert_eq!(
+            deployment,
+            AptosDeployment {
+                mailbox: Some(account),
+                interchain_gas_paymaster: Some(account),
+                multisig_ism: None,
+                validator_announce: Some(account),
+            }
+        );
+    }
+
+    #[test]
+    fn test_connectivity_report_flags_invalid_address() {
+        let package = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+        let typo = AccountAddress::from_hex_literal("0xa11cf").unwrap();
+        let deployment =
+            AptosDeployment::from_modules(package, ["mailbox", "igps", "multisig_ism"]);
+
+        let report = ConnectivityReport::from_lookups(
+            (package, Ok(deployment.clone())),
+            (package, Ok(deployment.clone())),
+            (
+                typo,
+                Err(ChainCommunicationError::from_other_str("account not found")),
+            ),
+        );
+        assert_eq!(report.mailbox, ContractHealth::Healthy);
+        assert_eq!(report.interchain_gas_paymaster, ContractHealth::Healthy);
+        assert!(matches!(
+            report.multisig_ism,
+            ContractHealth::Unreachable(_)
+        ));
+        assert!(!report.is_healthy());
+
+        let report = ConnectivityReport::from_lookups(
+            (package, Ok(deployment.clone())),
+            (package, Ok(deployment.clone())),
+            (
+                package,
+                Ok(AptosDeployment::from_modules(package, ["mailbox"])),
+            ),
+        );
+        assert_eq!(report.multisig_ism, ContractHealth::ModuleMissing);
+
+        let report = ConnectivityReport::from_lookups(
+            (package, Ok(deployment.clone())),
+            (package, Ok(deployment.clone())),
+            (package, Ok(deployment)),
+        );
+        assert!(report.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_blocks_fetches_each_height_once() {
+        let fetches = AtomicUsize::new(0);
+        let hashes = resolve_blocks_with(&[7, 5, 9, 7, 5], |height| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(H256::from_low_u64_be(height)) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        assert_eq!(hashes.len(), 3);
+        for height in [5, 7, 9] {
+            assert_eq!(hashes[&height], H256::from_low_u64_be(height));
+        }
+    }
+
+    #[test]
+    fn test_framework_addresses_convert_consistently() {
+        for (short, long) in [
+            (
+                "0x1",
+                "0x0000000000000000000000000000000000000000000000000000000000000001",
+            ),
+            (
+                "0x3",
+                "0x0000000000000000000000000000000000000000000000000000000000000003",
+            ),
+            (
+                "0x61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb",
+                "0x61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb",
+            ),
+        ] {
+            let address = AccountAddress::from_hex_literal(short).unwrap();
+            let h256 = account_address_to_h256(&address);
+            assert_eq!(convert_hex_string_to_h256(short).unwrap(), h256);
+            assert_eq!(convert_hex_string_to_h256(long).unwrap(), h256);
+            assert_eq!(
+                convert_hex_string_to_h256(&address.to_string()).unwrap(),
+                h256
+            );
+            assert_eq!(h256_to_account_address(&h256), address);
+        }
+        assert_eq!(
+            convert_hex_string_to_h256("0x1::aptos_coin::AptosCoin").unwrap(),
+            H256::from_low_u64_be(1)
+        );
+        assert!(convert_hex_string_to_h256("0x").is_err());
+        assert!(convert_hex_string_to_h256(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_account_address_to_h256_keeps_byte_order() {
+        let address = AccountAddress::from_hex_literal(
+            "0x61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb",
+        )
+        .unwrap();
+        assert_eq!(
+            account_address_to_h256(&address),
+            H256::from_str("61ad49767d3dd5d5e6e41563c3ca3e8600c52c350ca66014ee7f6874f28f5ddb")
+                .unwrap()
+        );
+        assert_eq!(
+            account_address_to_h256(&AccountAddress::ONE),
+            H256::from_low_u64_be(1)
+        );
+    }
+
+    #[test]
+    fn test_is_sequence_number_conflict() {
+        let api_error = |error_code| {
+            RestError::Api(AptosErrorResponse {
+                error: AptosError::new_with_error_code("rejected", error_code),
+                state: None,
+            })
+        };
+        assert!(is_sequence_number_conflict(&api_error(
+            AptosErrorCode::SequenceNumberTooOld
+        )));
+        assert!(is_sequence_number_conflict(&api_error(
+            AptosErrorCode::InvalidTransactionUpdate
+        )));
+        assert!(!is_sequence_number_conflict(&api_error(
+            AptosErrorCode::MempoolIsFull
+        )));
+        assert!(!is_sequence_number_conflict(&RestError::Timeout(
+            "submit_and_wait"
+        )));
+    }
+
+    fn local_account(seed: u8, sequence_number: u64) -> LocalAccount {
+        let private_key = Ed25519PrivateKey::try_from([seed; 32].as_slice()).unwrap();
+        let address = derive_account_address(&Ed25519PublicKey::from(&private_key).into());
+        LocalAccount::new(
+            address,
+            AccountKey::from_private_key(private_key),
+            sequence_number,
+        )
+    }
+
+    fn transaction_builder() -> aptos_sdk::transaction_builder::TransactionBuilder {
+        let payload = entry_function_payload(
+            AccountAddress::from_hex_literal("0xa11ce").unwrap(),
+            "hello_world",
+            "send_message",
+            vec![],
+            vec![],
+        )
+        .unwrap();
+        TransactionFactory::new(ChainId::test()).payload(payload)
+    }
+
+    #[test]
+    fn test_sign_transaction_with_fee_payer() {
+        let signer = local_account(1, 7);
+        let fee_payer = local_account(2, 3);
+
+        let signed_tx = sign_transaction(&signer, Some(&fee_payer), transaction_builder());
+
+        assert_eq!(signed_tx.sender(), signer.address());
+        assert_eq!(signed_tx.sequence_number(), 7);
+        match signed_tx.authenticator() {
+            TransactionAuthenticator::FeePayer {
+                fee_payer_address, ..
+            } => assert_eq!(fee_payer_address, fee_payer.address()),
+            authenticator => panic!("expected a fee payer authenticator, got {authenticator:?}"),
+        }
+        assert!(signed_tx.clone().check_signature().is_ok());
+        // only the sender's sequence number is used
+        assert_eq!(signer.sequence_number(), 8);
+        assert_eq!(fee_payer.sequence_number(), 3);
+    }
+
+    #[test]
+    fn test_sign_transaction_without_fee_payer() {
+        let signer = local_account(1, 7);
+
+        let signed_tx = sign_transaction(&signer, None, transaction_builder());
+
+        assert!(matches!(
+            signed_tx.authenticator(),
+            TransactionAuthenticator::Ed25519 { .. }
+        ));
+        assert_eq!(signer.sequence_number(), 8);
+    }
+
+    #[test]
+    fn test_entry_function_payload_validates_identifiers() {
+        let package = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+        let payload = entry_function_payload(
+            package,
+            "hello_world",
+            "send_message",
+            vec![],
+            vec![bcs::to_bytes(&14411u32).unwrap()],
+        )
+        .unwrap();
+        match payload {
+            TransactionPayload::EntryFunction(entry_function) => {
+                assert_eq!(entry_function.module().address(), &package);
+                assert_eq!(entry_function.module().name().as_str(), "hello_world");
+                assert_eq!(entry_function.function().as_str(), "send_message");
+                assert_eq!(entry_function.args(), &[bcs::to_bytes(&14411u32).unwrap()]);
+            }
+            _ => panic!("expected an entry function payload"),
+        }
+
+        assert!(entry_function_payload(package, "hello world", "send", vec![], vec![]).is_err());
+        assert!(entry_function_payload(package, "hello_world", "", vec![], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_pending_transaction_has_no_transaction_info() {
+        let pending: Transaction = serde_json::from_value(serde_json::json!({
+            "type": "pending_transaction",
+            "hash": format!("0x{}", "ab".repeat(32)),
+            "sender": "0xa11ce",
+            "sequence_number": "7",
+            "max_gas_amount": "100000",
+            "gas_unit_price": "100",
+            "expiration_timestamp_secs": "1700000000",
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [],
+            },
+        }))
+        .unwrap();
+
+        let err = transaction_info(&pending).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            AptosTransactionError::MissingTransactionInfo.to_string()
+        );
+    }
+
+    #[test]
+    fn test_outcome_from_info() {
+        let info: TransactionInfo = serde_json::from_value(serde_json::json!({
+            "version": "1234567",
+            "hash": format!("0x{}", "ab".repeat(32)),
+            "state_change_hash": format!("0x{}", "11".repeat(32)),
+            "event_root_hash": format!("0x{}", "22".repeat(32)),
+            "gas_used": "850",
+            "success": true,
+            "vm_status": "Executed successfully",
+            "accumulator_root_hash": format!("0x{}", "33".repeat(32)),
+            "changes": [],
+        }))
+        .unwrap();
+
+        let outcome = outcome_from_info(&info, 150).unwrap();
+        assert_eq!(outcome.transaction_id, H512::from(H256::repeat_byte(0xab)));
+        assert!(outcome.executed);
+        assert_eq!(outcome.gas_used, U256::from(850));
+        assert_eq!(outcome.gas_price, U256::from(150));
+    }
+
+    struct TestEvent {
+        block_height: &'static str,
+        transaction_hash: &'static str,
+    }
+
+    impl TxSpecificData for TestEvent {
+        fn block_height(&self) -> String {
+            self.block_height.to_owned()
+        }
+
+        fn transaction_hash(&self) -> String {
+            self.transaction_hash.to_owned()
+        }
+    }
+
+    #[test]
+    fn test_event_tx_data_is_decoded() {
+        let event = TestEvent {
+            block_height: "42",
+            transaction_hash: "0xab",
+        };
+        assert_eq!(event_block_height(&event).unwrap(), 42);
+        assert_eq!(
+            event_transaction_id(&event).unwrap(),
+            H512::from(H256::from_low_u64_be(0xab))
+        );
+    }
+
+    #[test]
+    fn test_malformed_event_tx_data_is_an_error() {
+        let event = TestEvent {
+            block_height: "latest",
+            transaction_hash: "not a hash",
+        };
+        assert!(event_block_height(&event).is_err());
+        assert!(event_transaction_id(&event).is_err());
+    }
+}
@@ -7,12 +7,15 @@ use solana_sdk::signature::Keypair;
 use tracing::info;
 use tracing::{instrument, warn};
 
-use crate::utils::{self, send_aptos_transaction};
-use crate::{convert_hex_string_to_h256, convert_keypair_to_aptos_account, AptosClient};
-use crate::{simulate_aptos_transaction, ConnectionConf};
+use crate::utils::{self, send_aptos_transaction, FromViewReturn};
+use crate::{
+    convert_hex_string_to_h256, convert_keypair_to_aptos_account, AptosClient, AptosRpcMetrics,
+};
+use crate::{simulate_aptos_transaction, ConnectionConf, GasPriceStrategy};
 use hyperlane_core::{
     Announcement, ChainCommunicationError, ChainResult, ContractLocator, HyperlaneChain,
-    HyperlaneContract, HyperlaneDomain, SignedType, TxOutcome, ValidatorAnnounce, H256, H512, U256,
+    HyperlaneContract, HyperlaneDomain, Signable, SignedType, TxOutcome, ValidatorAnnounce, H160,
+    H256, H512, U256,
 };
 
 use aptos_sdk::{
@@ -37,26 +40,92 @@ use once_cell::sync::Lazy;
 use std::str::FromStr;
 use url::Url;
 
+/// Checkpoint storage schemes a validator may announce
+const SUPPORTED_STORAGE_SCHEMES: [&str; 5] = ["s3", "gs", "file", "http", "https"];
+
+/// Errors raised by the Aptos validator announce before a transaction is submitted
+#[derive(thiserror::Error, Debug)]
+pub enum AptosValidatorAnnounceError {
+    /// The announced storage location isn't a URL with a supported scheme
+    #[error("Unsupported checkpoint storage location `{0}`, expected one of s3://, gs://, file://, http:// or https://")]
+    UnsupportedStorageLocation(String),
+}
+
+impl From<AptosValidatorAnnounceError> for ChainCommunicationError {
+    fn from(err: AptosValidatorAnnounceError) -> Self {
+        ChainCommunicationError::from_other(err)
+    }
+}
+
+/// Checks that `location` is a well-formed URL with a supported scheme, so
+/// malformed locations never end up on chain.
+fn validate_storage_location(location: &str) -> Result<(), AptosValidatorAnnounceError> {
+    match Url::parse(location) {
+        Ok(url) if SUPPORTED_STORAGE_SCHEMES.contains(&url.scheme()) => Ok(()),
+        _ => Err(AptosValidatorAnnounceError::UnsupportedStorageLocation(
+            location.to_owned(),
+        )),
+    }
+}
+
+/// Whether `signature` over `announcement` recovers to `validator`, an
+/// Ethereum address left-padded to 32 bytes as validators are stored on Aptos
+fn announcement_signed_by(validator: H256, announcement: &Announcement, signature: &[u8]) -> bool {
+    if validator[..12].iter().any(|byte| *byte != 0) {
+        return false;
+    }
+    utils::recover_eth_address(announcement.eth_signed_message_hash(), signature)
+        .map_or(false, |signer| H256::from(signer) == validator)
+}
+
 /// A reference to a ValidatorAnnounce contract on Aptos chain
-#[derive(Debug)]
 pub struct AptosValidatorAnnounce {
     package_address: AccountAddress,
     aptos_client: AptosClient,
     payer: Option<Keypair>,
     domain: HyperlaneDomain,
+    max_gas_amount: u64,
+    gas_price_strategy: GasPriceStrategy,
 }
 
 impl AptosValidatorAnnounce {
     /// Create a new Aptos ValidatorAnnounce
     pub fn new(conf: &ConnectionConf, locator: ContractLocator, payer: Option<Keypair>) -> Self {
-        let aptos_client = AptosClient::new(conf.url.to_string());
-        let package_address =
-            AccountAddress::from_bytes(<[u8; 32]>::from(locator.address)).unwrap();
+        let aptos_client = AptosClient::for_domain(conf.url.to_string(), &locator.domain);
+        Self::from_client(conf, locator, payer, aptos_client)
+    }
+
+    /// Create a ValidatorAnnounce sending its requests through `aptos_client`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        Self::from_client(conf, locator, payer, aptos_client)
+    }
+
+    /// Record metrics for the requests this ValidatorAnnounce makes
+    pub fn with_rpc_metrics(mut self, metrics: AptosRpcMetrics) -> Self {
+        self.aptos_client = self.aptos_client.with_metrics(metrics, &self.domain);
+        self
+    }
+
+    fn from_client(
+        conf: &ConnectionConf,
+        locator: ContractLocator,
+        payer: Option<Keypair>,
+        aptos_client: AptosClient,
+    ) -> Self {
+        let package_address = utils::h256_to_account_address(&locator.address);
         Self {
             package_address,
             aptos_client,
             payer,
             domain: locator.domain.clone(),
+            max_gas_amount: conf.max_gas_amount,
+            gas_price_strategy: conf.gas_price_strategy,
         }
     }
 
@@ -83,33 +152,86 @@ impl AptosValidatorAnnounce {
             "announce",
             vec![],
             vec![
-                bcs::to_bytes(
-                    &AccountAddress::from_hex_literal(&format!(
-                        "0x{}",
-                        hex::encode(announcement.value.validator.as_bytes())
-                    ))
-                    .unwrap(),
-                )
+                bcs::to_bytes(&utils::h256_to_account_address(
+                    &announcement.value.validator.into(),
+                ))
                 .unwrap(),
                 bcs::to_bytes(&serialized_signature.to_vec()).unwrap(),
                 bcs::to_bytes(&announcement.value.storage_location).unwrap(),
             ],
         );
 
-        let response =
-            send_aptos_transaction(&self.aptos_client, &mut signer_account, payload.clone())
-                .await?;
+        let response = send_aptos_transaction(
+            &self.aptos_client,
+            &mut signer_account,
+            None,
+            payload.clone(),
+            self.max_gas_amount,
+            self.gas_price_strategy,
+        )
+        .await?;
 
         // fetch transaction information from the response
-        let tx_hash = response.transaction_info().unwrap().hash.to_string();
+        let tx_hash = utils::transaction_info(&response)?.hash.to_string();
         let has_success = response.success();
         Ok((tx_hash, has_success))
     }
 }
 
+impl AptosValidatorAnnounce {
+    /// Returns whether `signature` is `validator`'s signature over an
+    /// announcement of `storage_location` for the mailbox this contract is
+    /// configured with, i.e. whether `announce` would accept it.
+    pub async fn verify_announcement(
+        &self,
+        validator: H256,
+        storage_location: &str,
+        signature: &[u8],
+    ) -> ChainResult<bool> {
+        let resource_type = format!(
+            "{}::validator_announce::ValidatorState",
+            self.package_address.to_hex_literal()
+        );
+        let state =
+            utils::get_resource_data(&self.aptos_client, self.package_address, &resource_type)
+                .await?;
+        let mailbox_address = utils::extract_resource_field(&state, "mailbox")?;
+        let mailbox_domain = utils::extract_resource_field(&state, "domain")?;
+        Ok(announcement_signed_by(
+            validator,
+            &Announcement {
+                validator: validator.into(),
+                mailbox_address: H256::from_view_return(&mailbox_address)?,
+                mailbox_domain: u32::from_view_return(&mailbox_domain)?,
+                storage_location: storage_location.to_owned(),
+            },
+            signature,
+        ))
+    }
+
+    /// Returns all validators that have made an announcement
+    pub async fn get_announced_validators(&self) -> ChainResult<Vec<H256>> {
+        utils::send_view_request_decoded(
+            &self.aptos_client,
+            self.package_address.to_hex_literal(),
+            "validator_announce".to_string(),
+            "get_announced_validators".to_string(),
+            vec![],
+            vec![],
+        )
+        .await
+    }
+}
+
+impl std::fmt::Debug for AptosValidatorAnnounce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self as &dyn HyperlaneContract)
+    }
+}
+
 impl HyperlaneContract for AptosValidatorAnnounce {
     fn address(&self) -> H256 {
-        H256(self.package_address.into_bytes())
+        utils::account_address_to_h256(&self.package_address)
     }
 }
 
@@ -119,9 +241,9 @@ impl HyperlaneChain for AptosValidatorAnnounce {
     }
 
     fn provider(&self) -> Box<dyn hyperlane_core::HyperlaneProvider> {
-        Box::new(crate::AptosHpProvider::new(
+        Box::new(crate::AptosHpProvider::from_client(
             self.domain.clone(),
-            self.aptos_client.path_prefix_string(),
+            self.aptos_client.clone(),
         ))
     }
 }
@@ -134,16 +256,10 @@ impl ValidatorAnnounce for AptosValidatorAnnounce {
     ) -> ChainResult<Vec<Vec<String>>> {
         let validator_addresses: Vec<serde_json::Value> = validators
             .iter()
-            .map(|v| {
-                serde_json::Value::String(
-                    AccountAddress::from_bytes(v.as_bytes())
-                        .unwrap()
-                        .to_hex_literal(),
-                )
-            })
+            .map(|v| serde_json::Value::String(utils::h256_to_account_address(v).to_hex_literal()))
             .collect();
 
-        let view_response = utils::send_view_request(
+        let mut view_result: Vec<Vec<String>> = utils::send_view_request_decoded(
             &self.aptos_client,
             self.package_address.to_hex_literal(),
             "validator_announce".to_string(),
@@ -152,9 +268,6 @@ impl ValidatorAnnounce for AptosValidatorAnnounce {
             vec![serde_json::Value::Array(validator_addresses)],
         )
         .await?;
-
-        let view_result = serde_json::from_str::<Vec<Vec<String>>>(&view_response[0].to_string());
-        let mut view_result = view_result.unwrap();
         if view_result.len() == 0 {
             view_result.push(vec![]);
         }
@@ -179,12 +292,17 @@ impl ValidatorAnnounce for AptosValidatorAnnounce {
             _announcement
         );
 
+        validate_storage_location(&_announcement.value.storage_location)?;
+
         let (tx_hash, is_success) = self
             .announce_contract_call(_announcement, _tx_gas_limit)
             .await
-            .map_err(|e| {
-                println!("tx error {}", e.to_string());
-                ChainCommunicationError::TransactionTimeout()
+            .map_err(|e| match e.downcast::<ChainCommunicationError>() {
+                Ok(err) => err,
+                Err(e) => {
+                    warn!(error = %e, "Failed to submit announce transaction");
+                    ChainCommunicationError::from_other(utils::AptosMoveCallError(e))
+                }
             })?;
 
         Ok(TxOutcome {
@@ -195,3 +313,102 @@ impl ValidatorAnnounce for AptosValidatorAnnounce {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use hyperlane_core::{Announcement, H160, H256};
+
+    use super::{announcement_signed_by, validate_storage_location};
+    use crate::utils::decode_view_return;
+
+    #[test]
+    fn test_decode_validator_addresses() {
+        let validators: Vec<H256> =
+            decode_view_return(&[serde_json::json!(["0xa11ce", "0xb0b"])]).unwrap();
+        assert_eq!(
+            validators,
+            vec![H256::from_low_u64_be(0xa11ce), H256::from_low_u64_be(0xb0b)]
+        );
+
+        assert!(decode_view_return::<Vec<H256>>(&[serde_json::json!([])])
+            .unwrap()
+            .is_empty());
+        assert!(decode_view_return::<Vec<H256>>(&[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_storage_location_accepts_supported_schemes() {
+        for location in [
+            "s3://hyperlane-validator-signatures/us-east-1",
+            "gs://hyperlane-validator-signatures",
+            "file:///tmp/checkpoints",
+            "http://localhost:8080/checkpoints",
+            "https://example.com/checkpoints",
+        ] {
+            assert!(validate_storage_location(location).is_ok(), "{location}");
+        }
+    }
+
+    #[test]
+    fn test_validate_storage_location_rejects_unknown_locations() {
+        for location in ["", "checkpoints", "/tmp/checkpoints", "ftp://example.com"] {
+            let err = validate_storage_location(location).unwrap_err();
+            assert!(err.to_string().contains(location), "{err}");
+        }
+    }
+
+    /// Announcement from the `verify_signature_test` of the Move package
+    fn mainnet_announcement() -> (H256, Announcement, Vec<u8>) {
+        let validator = H160::from_str("4c327ccb881a7542be77500b2833dc84c839e7b7").unwrap();
+        let announcement = Announcement {
+            validator,
+            mailbox_address: H160::from_str("35231d4c2d8b8adcb5617a638a0c4548684c7c70")
+                .unwrap()
+                .into(),
+            mailbox_domain: 1,
+            storage_location: "s3://hyperlane-mainnet2-ethereum-validator-0/us-east-1".to_owned(),
+        };
+        let signature = hex::decode("20ac937917284eaa3d67287278fc51875874241fffab5eb5fd8ae899a7074c5679be15f0bdb5b4f7594cefc5cba17df59b68ba3c55836053a23307db5a95610d1b").unwrap();
+        (validator.into(), announcement, signature)
+    }
+
+    #[test]
+    fn test_announcement_signed_by_validator() {
+        let (validator, announcement, signature) = mainnet_announcement();
+        assert!(announcement_signed_by(validator, &announcement, &signature));
+    }
+
+    #[test]
+    fn test_announcement_rejects_forgeries() {
+        let (validator, announcement, signature) = mainnet_announcement();
+
+        let other_location = Announcement {
+            storage_location: "s3://attacker-bucket/us-east-1".to_owned(),
+            ..announcement.clone()
+        };
+        assert!(!announcement_signed_by(
+            validator,
+            &other_location,
+            &signature
+        ));
+
+        let other_validator = H256::from(H160::repeat_byte(0x42));
+        assert!(!announcement_signed_by(
+            other_validator,
+            &announcement,
+            &signature
+        ));
+
+        let mut tampered = signature.clone();
+        tampered[10] ^= 0xff;
+        assert!(!announcement_signed_by(validator, &announcement, &tampered));
+
+        assert!(!announcement_signed_by(
+            validator,
+            &announcement,
+            &signature[..64]
+        ));
+    }
+}
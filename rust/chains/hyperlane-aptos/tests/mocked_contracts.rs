@@ -0,0 +1,30 @@
+//! Contracts built against a mocked node through the `test-utils` constructors
+
+use std::sync::Arc;
+
+use hyperlane_aptos::mock::MockAptosRpc;
+use hyperlane_aptos::{AptosClient, AptosMailbox, ConnectionConf};
+use hyperlane_core::{ContractLocator, HyperlaneDomain, KnownHyperlaneDomain, Mailbox, H256};
+
+#[tokio::test]
+async fn test_mocked_mailbox_answers_views() {
+    let rpc = Arc::new(
+        MockAptosRpc::default()
+            .with_view("mailbox::outbox_get_count", vec![serde_json::json!(7)])
+            .with_view("mailbox::delivered", vec![serde_json::json!(true)]),
+    );
+    let mailbox = AptosMailbox::with_client(
+        &ConnectionConf::test_default(),
+        ContractLocator {
+            domain: &HyperlaneDomain::Known(KnownHyperlaneDomain::AptosLocalnet1),
+            address: H256::repeat_byte(0x11),
+        },
+        None,
+        AptosClient::from_rpc(rpc.clone()),
+    )
+    .unwrap();
+
+    assert_eq!(mailbox.count(None).await.unwrap(), 7);
+    assert!(mailbox.delivered(H256::repeat_byte(0xab)).await.unwrap());
+    assert_eq!(rpc.requests("view").len(), 2);
+}
@@ -0,0 +1,20 @@
+use eyre::Result;
+use hyperlane_aptos::*;
+
+use crate::CoreMetrics;
+
+pub(crate) fn create_aptos_rpc_metrics(metrics: &CoreMetrics) -> Result<AptosRpcMetrics> {
+    Ok(AptosRpcMetrics::new(
+        metrics.new_int_counter(
+            "aptos_rpc_request_count",
+            APTOS_RPC_REQUEST_COUNT_HELP,
+            APTOS_RPC_REQUEST_COUNT_LABELS,
+        )?,
+        metrics.new_histogram(
+            "aptos_rpc_request_duration_seconds",
+            APTOS_RPC_REQUEST_DURATION_SECONDS_HELP,
+            APTOS_RPC_REQUEST_DURATION_SECONDS_LABELS,
+            prometheus::DEFAULT_BUCKETS.to_vec(),
+        )?,
+    ))
+}
@@ -13,9 +13,11 @@ use tokio::task::JoinHandle;
 use tracing::warn;
 
 use ethers_prometheus::{json_rpc_client::JsonRpcClientMetrics, middleware::MiddlewareMetrics};
+use hyperlane_aptos::AptosRpcMetrics;
 
 use crate::metrics::{
-    json_rpc_client::create_json_rpc_client_metrics, provider::create_provider_metrics,
+    aptos_rpc::create_aptos_rpc_metrics, json_rpc_client::create_json_rpc_client_metrics,
+    provider::create_provider_metrics,
 };
 
 /// Macro to prefix a string with the namespace.
@@ -51,6 +53,9 @@ pub struct CoreMetrics {
 
     /// Set of provider-specific metrics. These only need to get created once.
     provider_metrics: OnceLock<MiddlewareMetrics>,
+
+    /// Set of metrics that wrap the aptos REST client.
+    aptos_rpc_metrics: OnceLock<AptosRpcMetrics>,
 }
 
 impl CoreMetrics {
@@ -180,6 +185,7 @@ impl CoreMetrics {
 
             json_rpc_client_metrics: OnceLock::new(),
             provider_metrics: OnceLock::new(),
+            aptos_rpc_metrics: OnceLock::new(),
         })
     }
 
@@ -202,6 +208,16 @@ impl CoreMetrics {
             .clone()
     }
 
+    /// Create the aptos rpc client metrics attached to this core metrics
+    /// instance.
+    pub fn aptos_rpc_metrics(&self) -> AptosRpcMetrics {
+        self.aptos_rpc_metrics
+            .get_or_init(|| {
+                create_aptos_rpc_metrics(self).expect("Failed to create aptos rpc metrics!")
+            })
+            .clone()
+    }
+
     /// Create and register a new int gauge.
     pub fn new_int_gauge(
         &self,
@@ -6,5 +6,6 @@ pub const NAMESPACE: &str = "hyperlane";
 mod core;
 pub use self::core::*;
 
+mod aptos_rpc;
 mod json_rpc_client;
 mod provider;
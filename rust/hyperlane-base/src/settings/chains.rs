@@ -7,6 +7,7 @@ use ethers_prometheus::middleware::{
 use eyre::{eyre, Context, Result};
 use hyperlane_aptos as h_aptos;
 use hyperlane_core::{
+    config::{ConfigErrResultExt, ConfigParsingError, ConfigPath},
     AggregationIsm, CcipReadIsm, ContractLocator, HyperlaneAbi, HyperlaneDomain,
     HyperlaneDomainProtocol, HyperlaneMessage, HyperlaneProvider, HyperlaneSigner, IndexMode,
     InterchainGasPaymaster, InterchainGasPayment, InterchainSecurityModule, Mailbox, MultisigIsm,
@@ -32,6 +33,9 @@ pub struct ChainConf {
     pub domain: HyperlaneDomain,
     /// Signer configuration for this chain
     pub signer: Option<SignerConf>,
+    /// Further signers that submissions are rotated across, on chains that
+    /// support more than one signer
+    pub additional_signers: Vec<SignerConf>,
     /// Number of blocks until finality
     pub finality_blocks: u32,
     /// Addresses of contracts on the chain
@@ -69,6 +73,28 @@ impl ChainConnectionConf {
             Self::Aptos(_) => HyperlaneDomainProtocol::Aptos,
         }
     }
+
+    /// Check the core contract addresses under `cwp` against what this
+    /// protocol accepts, recording an error for each offending field.
+    pub(crate) fn validate_addresses(
+        &self,
+        addresses: &CoreContractAddresses,
+        cwp: &ConfigPath,
+        err: &mut ConfigParsingError,
+    ) {
+        if let Self::Aptos(_) = self {
+            for (field, address) in [
+                ("mailbox", addresses.mailbox),
+                (
+                    "interchain_gas_paymaster",
+                    addresses.interchain_gas_paymaster,
+                ),
+                ("validator_announce", addresses.validator_announce),
+            ] {
+                h_aptos::validate_module_address(field, address).take_err(err, || cwp + field);
+            }
+        }
+    }
 }
 
 /// Addresses for mailbox chain contracts
@@ -113,7 +139,12 @@ impl ChainConf {
             }
             ChainConnectionConf::Fuel(_) => todo!(),
             ChainConnectionConf::Sealevel(_) => todo!(),
-            ChainConnectionConf::Aptos(_) => todo!(),
+            ChainConnectionConf::Aptos(conf) => {
+                let provider =
+                    h_aptos::AptosHpProvider::new(self.domain.clone(), conf.url.to_string())
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics());
+                Ok(Box::new(provider) as Box<dyn HyperlaneProvider>)
+            }
         }
         .context(ctx)
     }
@@ -142,7 +173,11 @@ impl ChainConf {
             }
             ChainConnectionConf::Aptos(conf) => {
                 let keypair = self.aptos_signer().await.context(ctx)?;
+                let additional_payers = self.aptos_additional_signers().await.context(ctx)?;
                 h_aptos::AptosMailbox::new(conf, locator, keypair)
+                    .map(|m| m.with_additional_payers(additional_payers))
+                    .map(|m| m.with_rpc_metrics(metrics.aptos_rpc_metrics()))
+                    .map(|m| m.with_gas_paymaster(self.addresses.interchain_gas_paymaster))
                     .map(|m| Box::new(m) as Box<dyn Mailbox>)
                     .map_err(Into::into)
             }
@@ -177,7 +212,10 @@ impl ChainConf {
                 Ok(indexer as Box<dyn SequenceIndexer<HyperlaneMessage>>)
             }
             ChainConnectionConf::Aptos(conf) => {
-                let indexer = Box::new(h_aptos::AptosMailboxIndexer::new(conf, locator)?);
+                let indexer = Box::new(
+                    h_aptos::AptosMailboxIndexer::new(conf, locator)?
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(indexer as Box<dyn SequenceIndexer<HyperlaneMessage>>)
             }
         }
@@ -211,7 +249,10 @@ impl ChainConf {
                 Ok(indexer as Box<dyn SequenceIndexer<H256>>)
             }
             ChainConnectionConf::Aptos(conf) => {
-                let indexer = Box::new(h_aptos::AptosMailboxIndexer::new(conf, locator)?);
+                let indexer = Box::new(
+                    h_aptos::AptosMailboxIndexer::new(conf, locator)?
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(indexer as Box<dyn SequenceIndexer<H256>>)
             }
         }
@@ -246,7 +287,10 @@ impl ChainConf {
                 Ok(paymaster as Box<dyn InterchainGasPaymaster>)
             }
             ChainConnectionConf::Aptos(conf) => {
-                let paymaster = Box::new(h_aptos::AptosInterchainGasPaymaster::new(conf, &locator));
+                let paymaster = Box::new(
+                    h_aptos::AptosInterchainGasPaymaster::new(conf, &locator)
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(paymaster as Box<dyn InterchainGasPaymaster>)
             }
         }
@@ -283,9 +327,10 @@ impl ChainConf {
                 Ok(indexer as Box<dyn SequenceIndexer<InterchainGasPayment>>)
             }
             ChainConnectionConf::Aptos(conf) => {
-                let indexer = Box::new(h_aptos::AptosInterchainGasPaymasterIndexer::new(
-                    conf, locator,
-                ));
+                let indexer = Box::new(
+                    h_aptos::AptosInterchainGasPaymasterIndexer::new(conf, locator)
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(indexer as Box<dyn SequenceIndexer<InterchainGasPayment>>)
             }
         }
@@ -311,7 +356,10 @@ impl ChainConf {
             }
             ChainConnectionConf::Aptos(conf) => {
                 let keypair = self.aptos_signer().await.context("Announcing Validator")?;
-                let va = Box::new(h_aptos::AptosValidatorAnnounce::new(conf, locator, keypair));
+                let va = Box::new(
+                    h_aptos::AptosValidatorAnnounce::new(conf, locator, keypair)
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(va as Box<dyn ValidatorAnnounce>)
             }
         }
@@ -349,9 +397,10 @@ impl ChainConf {
             }
             ChainConnectionConf::Aptos(conf) => {
                 let keypair = self.aptos_signer().await.context(ctx)?;
-                let ism = Box::new(h_aptos::AptosInterchainSecurityModule::new(
-                    conf, locator, keypair,
-                ));
+                let ism = Box::new(
+                    h_aptos::AptosInterchainSecurityModule::new(conf, locator, keypair)
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(ism as Box<dyn InterchainSecurityModule>)
             }
         }
@@ -381,7 +430,10 @@ impl ChainConf {
             }
             ChainConnectionConf::Aptos(conf) => {
                 let keypair = self.aptos_signer().await.context(ctx)?;
-                let ism = Box::new(h_aptos::AptosMultisigISM::new(conf, locator, keypair));
+                let ism = Box::new(
+                    h_aptos::AptosMultisigISM::new(conf, locator, keypair)
+                        .with_rpc_metrics(metrics.aptos_rpc_metrics()),
+                );
                 Ok(ism as Box<dyn MultisigIsm>)
             }
         }
@@ -501,6 +553,14 @@ impl ChainConf {
         self.signer().await
     }
 
+    async fn aptos_additional_signers(&self) -> Result<Vec<h_aptos::Keypair>> {
+        let mut signers = Vec::with_capacity(self.additional_signers.len());
+        for conf in &self.additional_signers {
+            signers.push(conf.build::<h_aptos::Keypair>().await?);
+        }
+        Ok(signers)
+    }
+
     /// Get a clone of the ethereum metrics conf with correctly configured
     /// contract information.
     fn metrics_conf(
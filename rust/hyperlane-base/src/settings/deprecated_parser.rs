@@ -209,6 +209,8 @@ pub struct DeprecatedRawChainConf {
     name: Option<String>,
     domain: Option<StrOrInt>,
     pub(super) signer: Option<DeprecatedRawSignerConf>,
+    #[serde(default)]
+    additional_signers: Vec<DeprecatedRawSignerConf>,
     finality_blocks: Option<StrOrInt>,
     addresses: Option<DeprecatedRawCoreContractAddresses>,
     #[serde(flatten, default)]
@@ -268,6 +270,16 @@ impl FromRawConf<DeprecatedRawChainConf> for ChainConf {
                 .take_config_err(&mut err)
         });
 
+        let additional_signers = raw
+            .additional_signers
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, v)| -> Option<SignerConf> {
+                v.parse_config(&cwp.join("additional_signers").join(i.to_string()))
+                    .take_config_err(&mut err)
+            })
+            .collect();
+
         let finality_blocks = raw
             .finality_blocks
             .and_then(|v| {
@@ -285,12 +297,14 @@ impl FromRawConf<DeprecatedRawChainConf> for ChainConf {
         let metrics_conf = raw.metrics_conf.unwrap_or_default();
 
         cfg_unwrap_all!(cwp, err: [connection, domain, addresses]);
+        connection.validate_addresses(&addresses, &cwp.join("addresses"), &mut err);
 
         err.into_result(Self {
             connection,
             domain,
             addresses,
             signer,
+            additional_signers,
             finality_blocks,
             index,
             metrics_conf,
@@ -435,3 +449,59 @@ impl FromRawConf<DeprecatedRawCheckpointSyncerConf> for CheckpointSyncerConf {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use hyperlane_core::config::{ConfigPath, FromRawConf};
+
+    use super::DeprecatedRawChainConf;
+    use crate::settings::ChainConf;
+
+    const MAILBOX: &str = "0x476307c25c54b76b331a4e3422ae293ada422f5455efed1553cf4de1222a108f";
+
+    fn parse_aptos_chain(addresses: serde_json::Value) -> Result<ChainConf, String> {
+        let raw: DeprecatedRawChainConf = serde_json::from_value(serde_json::json!({
+            "name": "aptoslocalnet1",
+            "domain": 14411,
+            "addresses": addresses,
+            "protocol": "aptos",
+            "connection": { "url": "http://127.0.0.1:8080/v1" },
+        }))
+        .unwrap();
+        ChainConf::from_config(raw, &ConfigPath::default()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn aptos_chain_with_all_addresses() {
+        let conf = parse_aptos_chain(serde_json::json!({
+            "mailbox": MAILBOX,
+            "interchainGasPaymaster": "0xc5cb1f1ce6951226e9c46ce8d42eda1ac9774a0fef91e2910939119ef0c95568",
+            "validatorAnnounce": "0xa4a4eb4bab83650ba62cabe9ce429ad021b29c12f2fbf808768838255c7e191d",
+        }))
+        .unwrap();
+        assert_eq!(format!("{:?}", conf.addresses.mailbox), MAILBOX);
+    }
+
+    #[test]
+    fn aptos_chain_missing_mailbox() {
+        let err = parse_aptos_chain(serde_json::json!({
+            "interchainGasPaymaster": "0xc5cb1f1ce6951226e9c46ce8d42eda1ac9774a0fef91e2910939119ef0c95568",
+            "validatorAnnounce": "0xa4a4eb4bab83650ba62cabe9ce429ad021b29c12f2fbf808768838255c7e191d",
+        }))
+        .unwrap_err();
+        assert!(err.contains("config_path: `addresses.mailbox`"));
+        assert!(err.contains("Missing mailbox core contract address"));
+    }
+
+    #[test]
+    fn aptos_chain_zero_address() {
+        let err = parse_aptos_chain(serde_json::json!({
+            "mailbox": MAILBOX,
+            "interchainGasPaymaster": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "validatorAnnounce": "0xa4a4eb4bab83650ba62cabe9ce429ad021b29c12f2fbf808768838255c7e191d",
+        }))
+        .unwrap_err();
+        assert!(err.contains("config_path: `addresses.interchainGasPaymaster`"));
+        assert!(err.contains("`interchain_gas_paymaster` must be the address of a deployed module"));
+    }
+}
@@ -10,6 +10,7 @@ use std::{
     cmp::Reverse,
     collections::{HashMap, HashSet},
     default::Default,
+    time::Duration,
 };
 
 use eyre::{eyre, Context};
@@ -116,6 +117,16 @@ fn parse_chain(chain: ValueParser, name: &str) -> ConfigResult<ChainConf> {
         .get_opt_key("signer")
         .and_then(parse_signer)
         .end();
+    let additional_signers = chain
+        .chain(&mut err)
+        .get_opt_key("additionalSigners")
+        .into_array_iter()
+        .map(|signers| {
+            signers
+                .filter_map(|signer| parse_signer(signer).take_config_err(&mut err))
+                .collect()
+        })
+        .unwrap_or_default();
 
     // TODO(2214): is it correct to define finality blocks as `confirmations` and not `reorgPeriod`?
     // TODO(2214): should we rename `finalityBlocks` in ChainConf?
@@ -261,24 +272,99 @@ fn parse_chain(chain: ValueParser, name: &str) -> ConfigResult<ChainConf> {
                 .map(|url| ChainConnectionConf::Sealevel(h_sealevel::ConnectionConf { url }))
         }
         HyperlaneDomainProtocol::Aptos => {
+            let max_concurrent_submissions = chain
+                .chain(&mut err)
+                .get_opt_key("maxConcurrentSubmissions")
+                .parse_u32()
+                .unwrap_or(h_aptos::DEFAULT_MAX_CONCURRENT_SUBMISSIONS);
+            if max_concurrent_submissions == 0 {
+                Err::<(), _>(h_aptos::ConnectionConfError::ZeroMaxConcurrentSubmissions)
+                    .take_err(&mut err, || &chain.cwp + "max_concurrent_submissions");
+            }
+            let max_gas_amount = chain
+                .chain(&mut err)
+                .get_opt_key("maxGasAmount")
+                .parse_u64()
+                .unwrap_or(h_aptos::GAS_UNIT_LIMIT);
+            let skip_delivered_messages = chain
+                .chain(&mut err)
+                .get_opt_key("skipDeliveredMessages")
+                .parse_bool()
+                .unwrap_or(false);
+            let validate_metadata = chain
+                .chain(&mut err)
+                .get_opt_key("validateMetadata")
+                .parse_bool()
+                .unwrap_or(false);
+            let fixed_gas_price = chain
+                .chain(&mut err)
+                .get_opt_key("fixedGasPrice")
+                .parse_u64()
+                .end();
+            let gas_price_tip = chain
+                .chain(&mut err)
+                .get_opt_key("gasPriceTip")
+                .parse_u64()
+                .end();
+            let max_message_body_size = chain
+                .chain(&mut err)
+                .get_opt_key("maxMessageBodySize")
+                .parse_u32()
+                .unwrap_or(h_aptos::MAX_MESSAGE_BODY_BYTES);
+            let process_timeout = chain
+                .chain(&mut err)
+                .get_opt_key("processTimeoutSecs")
+                .parse_u64()
+                .end()
+                .map(Duration::from_secs)
+                .unwrap_or(h_aptos::DEFAULT_PROCESS_TIMEOUT);
+            let max_transaction_size = chain
+                .chain(&mut err)
+                .get_opt_key("maxTransactionSize")
+                .parse_u32()
+                .unwrap_or(h_aptos::DEFAULT_MAX_TRANSACTION_SIZE);
+            let enforce_gas_payment = chain
+                .chain(&mut err)
+                .get_opt_key("enforceGasPayment")
+                .parse_bool()
+                .unwrap_or(false);
             ParseChain::from_option(rpcs.into_iter().next(), &mut err)
                 .get_key("http")
                 .parse_from_str("Invalid http url")
                 .end()
-                .map(|url| ChainConnectionConf::Aptos(h_aptos::ConnectionConf { url }))
+                .map(|url| {
+                    ChainConnectionConf::Aptos(h_aptos::ConnectionConf {
+                        url,
+                        max_concurrent_submissions,
+                        max_gas_amount,
+                        skip_delivered_messages,
+                        validate_metadata,
+                        gas_price_strategy: h_aptos::GasPriceStrategy::from_settings(
+                            fixed_gas_price,
+                            gas_price_tip,
+                        ),
+                        max_message_body_size,
+                        process_timeout,
+                        max_transaction_size,
+                        enforce_gas_payment,
+                    })
+                })
         }
     };
 
     cfg_unwrap_all!(&chain.cwp, err: [connection, mailbox, interchain_gas_paymaster, validator_announce]);
+    let addresses = CoreContractAddresses {
+        mailbox,
+        interchain_gas_paymaster,
+        validator_announce,
+    };
+    connection.validate_addresses(&addresses, &chain.cwp, &mut err);
     err.into_result(ChainConf {
         domain,
         signer,
+        additional_signers,
         finality_blocks,
-        addresses: CoreContractAddresses {
-            mailbox,
-            interchain_gas_paymaster,
-            validator_announce,
-        },
+        addresses,
         connection,
         metrics_conf: Default::default(),
         index: IndexSettings {